@@ -4,7 +4,7 @@
 See [`execute-command-macro`](https://crates.io/crates/execute-command-macro).
 */
 
-use execute_command_tokens::command_tokens;
+use execute_command_tokens::{ParseOptions, split_env_assignments, tokenize};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{LitStr, parse_macro_input};
@@ -13,7 +13,8 @@ use syn::{LitStr, parse_macro_input};
 pub fn command(input: TokenStream) -> TokenStream {
     let s = parse_macro_input!(input as LitStr).value();
 
-    let tokens = command_tokens(s);
+    let (envs, tokens) = split_env_assignments(tokenize(s, ParseOptions::new()));
+    let tokens: Vec<String> = tokens.into_iter().map(|token| token.into_string()).collect();
 
     let tokens_length = tokens.len();
 
@@ -46,5 +47,21 @@ pub fn command(input: TokenStream) -> TokenStream {
         },
     };
 
+    let command = if envs.is_empty() {
+        command
+    } else {
+        let (names, values): (Vec<String>, Vec<String>) = envs.into_iter().unzip();
+
+        quote! {
+            {
+                let mut command = #command;
+
+                #(command.env(#names, #values);)*
+
+                command
+            }
+        }
+    };
+
     command.into()
 }
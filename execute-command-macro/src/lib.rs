@@ -23,10 +23,14 @@ let command = command_args!("program", "arg1", "arg2", "-opt1", "-opt2");
 /**
 Generate the statements at compile time to create a `Command` instance by a command string.
 
+Leading `NAME=value` tokens are set as environment variables of the command.
+
 ```rust
 #[macro_use] extern crate execute_command_macro;
 
 let command = command!("program arg1 arg2 -opt1 -opt2");
+
+let command = command!("RUST_LOG=debug program arg1");
 ```
 */
 pub use execute_command_macro_impl::command;
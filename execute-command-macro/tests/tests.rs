@@ -20,3 +20,13 @@ fn command_args() {
 
     assert_eq!(b"123\n", output.stdout.as_slice());
 }
+
+#[test]
+fn command_with_env_assignments() {
+    let mut command =
+        command!("EXECUTE_A=1 EXECUTE_B='2 3' sh -c 'echo \"$EXECUTE_A $EXECUTE_B\"'");
+
+    let output = command.output().unwrap();
+
+    assert_eq!(b"1 2 3\n", output.stdout.as_slice());
+}
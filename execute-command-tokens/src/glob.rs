@@ -0,0 +1,372 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::Token;
+
+/// What to do when a glob pattern does not match any path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NoMatchPolicy {
+    /// Keep the pattern as a literal argument, like the default behavior of POSIX shells.
+    #[default]
+    PassThrough,
+    /// Return a [`GlobError`], like the `failglob` option of bash.
+    Error,
+}
+
+/// The error returned when a glob pattern does not match any path and the policy is [`NoMatchPolicy::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobError {
+    pattern: String,
+}
+
+impl GlobError {
+    /// Get the pattern which did not match any path.
+    #[inline]
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+}
+
+impl Display for GlobError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "no matches found: {}", self.pattern)
+    }
+}
+
+impl Error for GlobError {}
+
+impl From<GlobError> for io::Error {
+    #[inline]
+    fn from(error: GlobError) -> Self {
+        io::Error::new(io::ErrorKind::NotFound, error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternItem {
+    Char(char),
+    AnyChar,
+    AnyString,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+#[derive(Debug)]
+enum Component {
+    Literal(String),
+    Pattern(Vec<PatternItem>),
+    /// `**`, which matches zero or more directories.
+    Recursive,
+}
+
+/// Expand the unquoted `*`, `?`, `[...]` and `**` patterns of tokens against the file system.
+///
+/// Relative patterns are matched against `dir`, and the matched paths stay relative, sorted in byte order. A leading `.` of a file name must be matched explicitly, and `**` does not descend into hidden directories or follow symbolic links. Tokens without unquoted patterns are kept as they are.
+pub fn expand_globs<I: IntoIterator<Item = Token>, P: AsRef<Path>>(
+    tokens: I,
+    dir: P,
+    policy: NoMatchPolicy,
+) -> Result<Vec<OsString>, GlobError> {
+    let dir = dir.as_ref();
+    let mut results = Vec::new();
+
+    for token in tokens {
+        let Some((absolute, components, dirs_only)) = parse_pattern(&token) else {
+            results.push(OsString::from(token.into_string()));
+
+            continue;
+        };
+
+        let base = if absolute { PathBuf::from("/") } else { dir.to_path_buf() };
+        let display_base = if absolute { PathBuf::from("/") } else { PathBuf::new() };
+
+        let mut matches = Vec::new();
+
+        expand_components(&base, &display_base, &components, dirs_only, &mut matches);
+
+        if matches.is_empty() {
+            match policy {
+                NoMatchPolicy::PassThrough => results.push(OsString::from(token.into_string())),
+                NoMatchPolicy::Error => {
+                    return Err(GlobError {
+                        pattern: token.into_string()
+                    });
+                },
+            }
+        } else {
+            matches.sort_unstable_by(|a, b| a.as_encoded_bytes().cmp(b.as_encoded_bytes()));
+            matches.dedup();
+
+            results.extend(matches);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Split a token into path components. Returns `None` if the token does not contain any unquoted pattern.
+fn parse_pattern(token: &Token) -> Option<(bool, Vec<Component>, bool)> {
+    let chars: Vec<(char, bool)> = token.quoted_chars().collect();
+
+    let absolute = matches!(chars.first(), Some(('/', _)));
+    let dirs_only = chars.len() > 1 && matches!(chars.last(), Some(('/', _)));
+
+    let mut components = Vec::new();
+    let mut has_pattern = false;
+
+    for component in chars.split(|(c, _)| *c == '/').filter(|component| !component.is_empty()) {
+        if component == [('*', false), ('*', false)] {
+            has_pattern = true;
+
+            components.push(Component::Recursive);
+
+            continue;
+        }
+
+        let items = parse_items(component);
+
+        if items.iter().all(|item| matches!(item, PatternItem::Char(_))) {
+            components.push(Component::Literal(component.iter().map(|(c, _)| c).collect()));
+        } else {
+            has_pattern = true;
+
+            components.push(Component::Pattern(items));
+        }
+    }
+
+    if has_pattern { Some((absolute, components, dirs_only)) } else { None }
+}
+
+fn parse_items(chars: &[(char, bool)]) -> Vec<PatternItem> {
+    let mut items = Vec::with_capacity(chars.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (c, quoted) = chars[i];
+
+        i += 1;
+
+        if quoted {
+            items.push(PatternItem::Char(c));
+
+            continue;
+        }
+
+        match c {
+            '*' => {
+                if items.last() != Some(&PatternItem::AnyString) {
+                    items.push(PatternItem::AnyString);
+                }
+            },
+            '?' => items.push(PatternItem::AnyChar),
+            '[' => match parse_class(&chars[i..]) {
+                Some((item, length)) => {
+                    items.push(item);
+
+                    i += length;
+                },
+                None => items.push(PatternItem::Char(c)),
+            },
+            _ => items.push(PatternItem::Char(c)),
+        }
+    }
+
+    items
+}
+
+/// Parse a bracket expression after `[`. Returns the item and the number of characters consumed, or `None` if it is not closed.
+fn parse_class(chars: &[(char, bool)]) -> Option<(PatternItem, usize)> {
+    let mut i = 0;
+
+    let negated = matches!(chars.first(), Some(('!' | '^', false)));
+
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let mut first = true;
+
+    loop {
+        let (c, quoted) = *chars.get(i)?;
+
+        i += 1;
+
+        // A `]` right after the opening bracket is a literal character.
+        if c == ']' && !quoted && !first {
+            return Some((
+                PatternItem::Class {
+                    negated,
+                    ranges,
+                },
+                i,
+            ));
+        }
+
+        first = false;
+
+        match (chars.get(i), chars.get(i + 1)) {
+            (Some(('-', false)), Some(&(end, end_quoted))) if end != ']' || end_quoted => {
+                ranges.push((c, end));
+
+                i += 2;
+            },
+            _ => ranges.push((c, c)),
+        }
+    }
+}
+
+/// Match a name against a pattern. A mismatch after a `*` only backtracks to the last `*`, because the earlier ones can never need to match more, so the time is bounded by the product of the lengths instead of growing exponentially with the number of `*`.
+fn matches_pattern(items: &[PatternItem], name: &[char]) -> bool {
+    let mut item_index = 0;
+    let mut name_index = 0;
+
+    // The position after the last `*`, and the position in the name where it is retried.
+    let mut backtrack = None;
+
+    while name_index < name.len() {
+        match items.get(item_index) {
+            Some(PatternItem::AnyString) => {
+                item_index += 1;
+                backtrack = Some((item_index, name_index));
+
+                continue;
+            },
+            Some(item) if matches_char(item, name[name_index]) => {
+                item_index += 1;
+                name_index += 1;
+
+                continue;
+            },
+            _ => (),
+        }
+
+        // Let the last `*` match one more character.
+        match backtrack {
+            Some((star_item_index, star_name_index)) => {
+                item_index = star_item_index;
+                name_index = star_name_index + 1;
+                backtrack = Some((star_item_index, name_index));
+            },
+            None => return false,
+        }
+    }
+
+    items[item_index..].iter().all(|item| matches!(item, PatternItem::AnyString))
+}
+
+fn matches_char(item: &PatternItem, c: char) -> bool {
+    match item {
+        PatternItem::Char(expected) => c == *expected,
+        PatternItem::AnyChar => true,
+        PatternItem::Class {
+            negated,
+            ranges,
+        } => ranges.iter().any(|(start, end)| (start..=end).contains(&&c)) != *negated,
+        PatternItem::AnyString => false,
+    }
+}
+
+/// List the non-hidden entries of a directory. Unreadable directories are treated as empty, like shells do.
+fn read_entries(dir: &Path) -> Vec<(OsString, bool)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let is_dir = entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false);
+
+            (entry.file_name(), is_dir)
+        })
+        .collect()
+}
+
+fn expand_components(
+    path: &Path,
+    display_path: &Path,
+    components: &[Component],
+    dirs_only: bool,
+    matches: &mut Vec<OsString>,
+) {
+    let Some((component, rest)) = components.split_first() else {
+        let exists = if dirs_only { path.is_dir() } else { fs::symlink_metadata(path).is_ok() };
+
+        if exists {
+            let mut display_path = display_path.as_os_str().to_os_string();
+
+            if dirs_only {
+                display_path.push("/");
+            }
+
+            matches.push(display_path);
+        }
+
+        return;
+    };
+
+    match component {
+        Component::Literal(name) => {
+            expand_components(&path.join(name), &display_path.join(name), rest, dirs_only, matches);
+        },
+        Component::Pattern(items) => {
+            let explicit_dot = matches!(items.first(), Some(PatternItem::Char('.')));
+
+            for (name, _) in read_entries(path) {
+                let name_chars: Vec<char> = name.to_string_lossy().chars().collect();
+
+                if name_chars.first() == Some(&'.') && !explicit_dot {
+                    continue;
+                }
+
+                if matches_pattern(items, &name_chars) {
+                    expand_components(
+                        &path.join(&name),
+                        &display_path.join(&name),
+                        rest,
+                        dirs_only,
+                        matches,
+                    );
+                }
+            }
+        },
+        Component::Recursive => {
+            if rest.is_empty() {
+                // A trailing `**` matches every file and directory below, like `**/*`.
+                let any = [Component::Pattern(vec![PatternItem::AnyString])];
+
+                expand_recursive(path, display_path, &any, dirs_only, matches);
+            } else {
+                expand_recursive(path, display_path, rest, dirs_only, matches);
+            }
+        },
+    }
+}
+
+fn expand_recursive(
+    path: &Path,
+    display_path: &Path,
+    rest: &[Component],
+    dirs_only: bool,
+    matches: &mut Vec<OsString>,
+) {
+    expand_components(path, display_path, rest, dirs_only, matches);
+
+    for (name, is_dir) in read_entries(path) {
+        if is_dir && !name.as_encoded_bytes().starts_with(b".") {
+            expand_recursive(
+                &path.join(&name),
+                &display_path.join(&name),
+                rest,
+                dirs_only,
+                matches,
+            );
+        }
+    }
+}
@@ -4,86 +4,210 @@
 Parse command strings.
 
 See [`execute`](https://crates.io/crates/execute).
+
+## Comments and Line Continuations
+
+Command templates stored in configuration files can be parsed with `ParseOptions::script()`, which allows `#` comments and backslash-newline line continuations.
+
+```rust
+use execute_command_tokens::{ParseOptions, command_tokens_with_options, script_tokens};
+
+let tokens = command_tokens_with_options("program arg1 \\\n    arg2 # a comment", ParseOptions::script());
+
+assert_eq!(vec!["program", "arg1", "arg2"], tokens);
+
+let commands = script_tokens("# build\nprogram1 arg1\n\nprogram2 \\\n    arg2\n", ParseOptions::script());
+
+assert_eq!(vec![vec!["program1", "arg1"], vec!["program2", "arg2"]], commands);
+```
+
+## ANSI-C Quoting
+
+Control characters can be written in command strings by enabling bash-style `$'...'` quoting.
+
+```rust
+use execute_command_tokens::{ParseOptions, command_tokens_with_options};
+
+let tokens = command_tokens_with_options(
+    r"printf $'%s\n' $'\x1B[1mbold\e[0m'",
+    ParseOptions::new().ansi_c_quoting(true),
+);
+
+assert_eq!(vec!["printf", "%s\n", "\x1B[1mbold\x1B[0m"], tokens);
+```
+
+## Tilde Expansion
+
+With `ParseOptions::tilde_expansion(true)`, a leading unquoted `~` or `~user` is expanded into a home directory. The [`expand_tildes`] function accepts any [`HomeDirs`] source, such as a closure.
+
+```rust
+use std::path::PathBuf;
+
+use execute_command_tokens::{ParseOptions, Token, expand_tildes, tokenize};
+
+let tokens = tokenize("~/bin/tool --config ~/.tool.toml '~/literal'", ParseOptions::new());
+
+let tokens = expand_tildes(tokens, &|user: Option<&str>| match user {
+    None => Some(PathBuf::from("/home/operator")),
+    Some(_) => None,
+});
+
+assert_eq!(
+    vec!["/home/operator/bin/tool", "--config", "/home/operator/.tool.toml", "~/literal"],
+    tokens.iter().map(Token::as_str).collect::<Vec<_>>()
+);
+```
+
+## Environment Variable Assignments
+
+Leading `NAME=value` tokens can be separated from the program and its arguments.
+
+```rust
+use execute_command_tokens::{ParseOptions, Token, split_env_assignments, tokenize};
+
+let (envs, tokens) =
+    split_env_assignments(tokenize("RUST_LOG=debug LC_ALL=C program --flag=1", ParseOptions::new()));
+
+assert_eq!(
+    vec![("RUST_LOG".to_string(), "debug".to_string()), ("LC_ALL".to_string(), "C".to_string())],
+    envs
+);
+assert_eq!(vec!["program", "--flag=1"], tokens.iter().map(Token::as_str).collect::<Vec<_>>());
+```
+
+## Here-documents and Here-strings
+
+With `ParseOptions::here_documents(true)`, the stdin data of a command can be written in the command string by a `<<<word` here-string or a `<<DELIMITER` here-document.
+
+```rust
+use execute_command_tokens::{ParseOptions, parse_command};
+
+let command = parse_command(
+    "psql -d app <<'SQL'\nSELECT 1;\nSQL",
+    ParseOptions::new().here_documents(true),
+);
+
+assert_eq!(Some("SELECT 1;\n"), command.input.as_deref());
+
+let command = parse_command("bc <<< '1 + 1'", ParseOptions::new().here_documents(true));
+
+assert_eq!(Some("1 + 1\n"), command.input.as_deref());
+```
+
+## Glob Expansion
+
+Globs are not expanded while parsing. The [`tokenize`] function keeps the quoting information of tokens so that [`expand_globs`] can expand the unquoted `*`, `?`, `[...]` and `**` patterns against a directory afterwards, leaving quoted patterns literal.
+
+```rust,no_run
+use execute_command_tokens::{NoMatchPolicy, ParseOptions, expand_globs, tokenize};
+
+let tokens = tokenize("rm -- *.log 'literal*.log'", ParseOptions::new());
+
+let args = expand_globs(tokens, "/path/to/dir", NoMatchPolicy::Error).unwrap();
+```
 */
 
+mod glob;
+mod options;
+mod parsed_command;
+mod parser;
+mod tilde;
+mod token;
+
+pub use glob::{GlobError, NoMatchPolicy, expand_globs};
+pub use options::ParseOptions;
+pub use parsed_command::ParsedCommand;
+use parser::Parser;
+pub use tilde::{HomeDirs, SystemHomeDirs, expand_tildes};
+pub use token::Token;
+
 /// Parse a command string into program and argument tokens.
 ///
 /// Whitespace separates tokens, single quotes and double quotes keep whitespace inside a token, and a backslash escapes the next character.
 /// Adjacent quoted and unquoted parts are joined into one token, so `a"b"` and `'a'b` both become `ab`.
+/// An empty quoted part only makes an empty token when nothing is joined to it, so `a'' b` becomes `a` and `b`. Earlier versions emitted a stray empty token after such a token when it was followed by more than one whitespace character.
 /// This parser is not a shell parser: it does not expand variables, globs, redirections, pipes, or command substitutions.
+/// Use [`tokenize`] and [`expand_globs`] to expand globs afterwards.
 /// Unmatched quotes and a trailing backslash are handled as best-effort tolerant input instead of returning an error.
+#[inline]
 pub fn command_tokens<S: AsRef<str>>(cmd: S) -> Vec<String> {
-    let cmd = cmd.as_ref();
-
-    let mut tokens = Vec::with_capacity(1);
-    let mut string_buffer = String::new();
-
-    let mut append_mode = false;
-    let mut quote_mode = false;
-    let mut quote_mode_ending = false; // to deal with '123''456' -> 123456
-    let mut quote_char = ' ';
-    let mut escaping = false;
-
-    for c in cmd.chars() {
-        if escaping {
-            append_mode = true;
-            escaping = false;
-
-            string_buffer.push(c);
-        } else if c.is_whitespace() {
-            if append_mode {
-                if quote_mode {
-                    string_buffer.push(c);
-                } else {
-                    append_mode = false;
-
-                    tokens.push(string_buffer);
-                    string_buffer = String::new();
-                }
-            } else if quote_mode_ending {
-                quote_mode_ending = false;
-
-                tokens.push(string_buffer);
-                string_buffer = String::new();
-            }
-        } else {
-            match c {
-                '"' | '\'' => {
-                    if append_mode {
-                        if quote_mode {
-                            if quote_char == c {
-                                append_mode = false;
-                                quote_mode = false;
-                                quote_mode_ending = true;
-                            } else {
-                                string_buffer.push(c);
-                            }
-                        } else {
-                            quote_mode = true;
-                            quote_char = c;
-                        }
-                    } else {
-                        append_mode = true;
-                        quote_mode = true;
-                        quote_char = c;
-                    }
-                },
-                '\\' => {
-                    escaping = true;
-                },
-                _ => {
-                    append_mode = true;
-                    escaping = false;
-
-                    string_buffer.push(c);
-                },
-            }
+    command_tokens_with_options(cmd, ParseOptions::new())
+}
+
+/// Parse a command string into program and argument tokens with the given options.
+///
+/// Newlines are treated as whitespace, so the whole string is parsed as one command. See [`command_tokens`] for the basic rules.
+#[inline]
+pub fn command_tokens_with_options<S: AsRef<str>>(cmd: S, options: ParseOptions) -> Vec<String> {
+    tokenize(cmd, options).into_iter().map(Token::into_string).collect()
+}
+
+/// Parse a multi-line script into the tokens of its commands, one command per logical line.
+///
+/// Unquoted newlines separate commands. Lines without any token, such as blank lines and comment-only lines, are skipped.
+#[inline]
+pub fn script_tokens<S: AsRef<str>>(script: S, options: ParseOptions) -> Vec<Vec<String>> {
+    tokenize_script(script, options)
+        .into_iter()
+        .map(|tokens| tokens.into_iter().map(Token::into_string).collect())
+        .collect()
+}
+
+/// Parse a command string into [`Token`]s, which keep the quoting information needed by later expansion passes such as [`expand_globs`].
+#[inline]
+pub fn tokenize<S: AsRef<str>>(cmd: S, options: ParseOptions) -> Vec<Token> {
+    parse_command(cmd, options).tokens
+}
+
+/// Parse a multi-line script into the [`Token`]s of its commands, one command per logical line. See [`script_tokens`].
+#[inline]
+pub fn tokenize_script<S: AsRef<str>>(script: S, options: ParseOptions) -> Vec<Vec<Token>> {
+    parse_script(script, options).into_iter().map(|command| command.tokens).collect()
+}
+
+/// Parse a command string into [`Token`]s along with the stdin data given by a here-document or a here-string. See [`ParseOptions::here_documents`].
+pub fn parse_command<S: AsRef<str>>(cmd: S, options: ParseOptions) -> ParsedCommand {
+    let mut parser = Parser::new(cmd.as_ref(), options);
+
+    let tokens = parser.next_command(false).unwrap_or_default();
+
+    ParsedCommand {
+        tokens: expand_tokens(tokens, options), input: parser.take_input()
+    }
+}
+
+/// Parse a multi-line script into the [`Token`]s of its commands along with their stdin data, one command per logical line. The body of a here-document is not a part of any logical line. See [`script_tokens`] and [`ParseOptions::here_documents`].
+pub fn parse_script<S: AsRef<str>>(script: S, options: ParseOptions) -> Vec<ParsedCommand> {
+    let mut parser = Parser::new(script.as_ref(), options);
+    let mut commands = Vec::new();
+
+    while let Some(tokens) = parser.next_command(true) {
+        if !tokens.is_empty() {
+            commands.push(ParsedCommand {
+                tokens: expand_tokens(tokens, options),
+                input:  parser.take_input(),
+            });
         }
     }
 
-    if append_mode || quote_mode_ending {
-        tokens.push(string_buffer);
+    commands
+}
+
+/// Split the leading environment variable assignments, such as `RUST_LOG=debug` in `RUST_LOG=debug program`, from the program and argument tokens. See [`Token::as_assignment`].
+pub fn split_env_assignments(tokens: Vec<Token>) -> (Vec<(String, String)>, Vec<Token>) {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut envs = Vec::new();
+
+    while let Some((name, value)) = tokens.peek().and_then(Token::as_assignment) {
+        envs.push((name.to_string(), value.to_string()));
+
+        tokens.next();
     }
 
-    tokens
+    (envs, tokens.collect())
+}
+
+#[inline]
+fn expand_tokens(tokens: Vec<Token>, options: ParseOptions) -> Vec<Token> {
+    if options.tilde_expansion { expand_tildes(tokens, &SystemHomeDirs) } else { tokens }
 }
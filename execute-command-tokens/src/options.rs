@@ -0,0 +1,73 @@
+/// Options for parsing command strings.
+///
+/// The default options parse command strings exactly like [`command_tokens`](crate::command_tokens).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    pub(crate) comments:          bool,
+    pub(crate) line_continuation: bool,
+    pub(crate) ansi_c_quoting:    bool,
+    pub(crate) tilde_expansion:   bool,
+    pub(crate) here_documents:    bool,
+}
+
+impl ParseOptions {
+    /// Create the default options.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            comments:          false,
+            line_continuation: false,
+            ansi_c_quoting:    false,
+            tilde_expansion:   false,
+            here_documents:    false,
+        }
+    }
+
+    /// Create options suitable for multi-line command templates, with comments and line continuations enabled.
+    #[inline]
+    pub const fn script() -> Self {
+        Self::new().comments(true).line_continuation(true)
+    }
+
+    /// Treat an unquoted `#` at the start of a token as the beginning of a comment which lasts until the end of the line.
+    #[inline]
+    pub const fn comments(mut self, enable: bool) -> Self {
+        self.comments = enable;
+
+        self
+    }
+
+    /// Treat a backslash followed by a newline as a line continuation which is removed, instead of an escaped newline character.
+    #[inline]
+    pub const fn line_continuation(mut self, enable: bool) -> Self {
+        self.line_continuation = enable;
+
+        self
+    }
+
+    /// Treat `$'...'` as a bash-style ANSI-C quoted part, in which backslash escape sequences such as `\n`, `\t`, `\x1B`, `\033`, `\u00E9` and `\cA` are decoded.
+    #[inline]
+    pub const fn ansi_c_quoting(mut self, enable: bool) -> Self {
+        self.ansi_c_quoting = enable;
+
+        self
+    }
+
+    /// Expand a leading unquoted `~` or `~user` of each token into a home directory, using [`SystemHomeDirs`](crate::SystemHomeDirs). Use [`expand_tildes`](crate::expand_tildes) to provide the home directories yourself.
+    #[inline]
+    pub const fn tilde_expansion(mut self, enable: bool) -> Self {
+        self.tilde_expansion = enable;
+
+        self
+    }
+
+    /// Treat unquoted `<<<word` here-strings and `<<DELIMITER` here-documents as the stdin data of the command instead of arguments. Use [`parse_command`](crate::parse_command) or [`parse_script`](crate::parse_script) to get the data.
+    ///
+    /// Like POSIX shells, a here-document body is taken literally if any part of its delimiter is quoted. Otherwise, `\\`, `\$` and `` \` `` are unescaped and backslash-newline sequences are removed. `<<-DELIMITER` strips leading tabs from the body lines and the delimiter line. Parameter expansion is never performed.
+    #[inline]
+    pub const fn here_documents(mut self, enable: bool) -> Self {
+        self.here_documents = enable;
+
+        self
+    }
+}
@@ -0,0 +1,10 @@
+use crate::Token;
+
+/// A parsed command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedCommand {
+    /// The program and argument tokens, including leading environment variable assignments.
+    pub tokens: Vec<Token>,
+    /// The stdin data given by the last here-document or here-string, if any.
+    pub input:  Option<String>,
+}
@@ -0,0 +1,349 @@
+use std::{iter::Peekable, mem, str::Chars};
+
+use crate::{ParseOptions, Token};
+
+struct HereDocument {
+    delimiter:  String,
+    quoted:     bool,
+    strip_tabs: bool,
+}
+
+pub(crate) struct Parser<'a> {
+    chars:          Peekable<Chars<'a>>,
+    options:        ParseOptions,
+    here_documents: Vec<HereDocument>,
+    input:          Option<String>,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    pub(crate) fn new(cmd: &'a str, options: ParseOptions) -> Self {
+        Self {
+            chars: cmd.chars().peekable(),
+            options,
+            here_documents: Vec::new(),
+            input: None,
+        }
+    }
+
+    /// Parse tokens until the end of the input, or until an unquoted newline if `split_lines` is `true`. Returns `None` if the input has been consumed.
+    ///
+    /// The stdin data of the parsed command can be taken by `take_input` afterwards.
+    pub(crate) fn next_command(&mut self, split_lines: bool) -> Option<Vec<Token>> {
+        self.chars.peek()?;
+
+        self.input = None;
+
+        let mut tokens = Vec::with_capacity(1);
+        let mut token: Option<Token> = None;
+
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(c) = self.next_escaped() {
+                        token.get_or_insert_with(Token::default).push(c, true);
+                    }
+                },
+                '"' | '\'' => {
+                    self.read_quoted(c, token.get_or_insert_with(Token::default));
+                },
+                '$' if self.options.ansi_c_quoting && self.chars.next_if_eq(&'\'').is_some() => {
+                    self.read_ansi_c_quoted(token.get_or_insert_with(Token::default));
+                },
+                '#' if self.options.comments && token.is_none() => {
+                    // The newline is left for the next iteration because it still separates lines.
+                    while self.chars.next_if(|c| *c != '\n').is_some() {}
+                },
+                '<' if self.options.here_documents && self.chars.next_if_eq(&'<').is_some() => {
+                    tokens.extend(token.take());
+
+                    self.read_redirection();
+                },
+                '\n' => {
+                    // Here-document bodies start at the line after their operators.
+                    self.read_here_document_bodies();
+
+                    if split_lines {
+                        tokens.extend(token);
+
+                        return Some(tokens);
+                    }
+
+                    tokens.extend(token.take());
+                },
+                _ if c.is_whitespace() => {
+                    tokens.extend(token.take());
+                },
+                _ => {
+                    token.get_or_insert_with(Token::default).push(c, false);
+                },
+            }
+        }
+
+        self.read_here_document_bodies();
+
+        tokens.extend(token);
+
+        Some(tokens)
+    }
+
+    /// Take the stdin data given by the last here-document or here-string of the parsed command.
+    #[inline]
+    pub(crate) fn take_input(&mut self) -> Option<String> {
+        self.input.take()
+    }
+
+    /// Read a here-string or a here-document operator after `<<`.
+    fn read_redirection(&mut self) {
+        if self.chars.next_if_eq(&'<').is_some() {
+            let (word, _) = self.read_word().unwrap_or_default();
+
+            let mut input = word.into_string();
+
+            input.push('\n');
+
+            self.input = Some(input);
+        } else {
+            let strip_tabs = self.chars.next_if_eq(&'-').is_some();
+
+            if let Some((delimiter, quoted)) = self.read_word() {
+                self.here_documents.push(HereDocument {
+                    delimiter: delimiter.into_string(),
+                    quoted,
+                    strip_tabs,
+                });
+            }
+        }
+    }
+
+    /// Read a word after an operator, skipping leading blanks. Returns the word and whether any part of it was quoted or escaped, or `None` if there is no word on this line.
+    fn read_word(&mut self) -> Option<(Token, bool)> {
+        while self.chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+
+        let mut token: Option<Token> = None;
+        let mut quoted = false;
+
+        while let Some(c) = self.chars.next_if(|c| !c.is_whitespace()) {
+            match c {
+                '\\' => {
+                    quoted = true;
+
+                    if let Some(c) = self.next_escaped() {
+                        token.get_or_insert_with(Token::default).push(c, true);
+                    }
+                },
+                '"' | '\'' => {
+                    quoted = true;
+
+                    self.read_quoted(c, token.get_or_insert_with(Token::default));
+                },
+                '$' if self.options.ansi_c_quoting && self.chars.next_if_eq(&'\'').is_some() => {
+                    quoted = true;
+
+                    self.read_ansi_c_quoted(token.get_or_insert_with(Token::default));
+                },
+                _ => {
+                    token.get_or_insert_with(Token::default).push(c, false);
+                },
+            }
+        }
+
+        token.map(|token| (token, quoted))
+    }
+
+    /// Read the bodies of the pending here-documents. An unterminated body lasts until the end of the input.
+    fn read_here_document_bodies(&mut self) {
+        for here_document in mem::take(&mut self.here_documents) {
+            let mut body = String::new();
+
+            while let Some(line) = self.read_line() {
+                let line =
+                    if here_document.strip_tabs { line.trim_start_matches('\t') } else { &line };
+
+                if line.trim_end_matches('\n').trim_end_matches('\r') == here_document.delimiter {
+                    break;
+                }
+
+                body.push_str(line);
+            }
+
+            // Like POSIX shells, backslashes in the body are only processed if no part of the delimiter is quoted. Parameter expansion is never performed.
+            if !here_document.quoted {
+                body = unescape_here_document(&body);
+            }
+
+            self.input = Some(body);
+        }
+    }
+
+    /// Read a line including its newline character. Returns `None` at the end of the input.
+    fn read_line(&mut self) -> Option<String> {
+        self.chars.peek()?;
+
+        let mut line = String::new();
+
+        for c in self.chars.by_ref() {
+            line.push(c);
+
+            if c == '\n' {
+                break;
+            }
+        }
+
+        Some(line)
+    }
+
+    /// Read the character after a backslash. Returns `None` for a line continuation or a trailing backslash.
+    fn next_escaped(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        if self.options.line_continuation {
+            match c {
+                '\n' => return None,
+                '\r' if self.chars.next_if_eq(&'\n').is_some() => return None,
+                _ => (),
+            }
+        }
+
+        Some(c)
+    }
+
+    /// Read a quoted part until the closing `quote_char`. An unmatched quote is tolerated and lasts until the end of the input.
+    fn read_quoted(&mut self, quote_char: char, token: &mut Token) {
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(c) = self.next_escaped() {
+                        token.push(c, true);
+                    }
+                },
+                _ if c == quote_char => return,
+                _ => token.push(c, true),
+            }
+        }
+    }
+
+    /// Read an ANSI-C quoted part after `$'` until the closing single quote. Decoded bytes which are not valid UTF-8 are replaced with `U+FFFD`.
+    fn read_ansi_c_quoted(&mut self, token: &mut Token) {
+        let mut bytes = Vec::new();
+
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\\' => self.read_ansi_c_escape(&mut bytes),
+                '\'' => break,
+                _ => push_char(&mut bytes, c),
+            }
+        }
+
+        token.push_str(&String::from_utf8_lossy(&bytes), true);
+    }
+
+    fn read_ansi_c_escape(&mut self, bytes: &mut Vec<u8>) {
+        let Some(c) = self.chars.next() else {
+            bytes.push(b'\\');
+
+            return;
+        };
+
+        match c {
+            'a' => bytes.push(0x07),
+            'b' => bytes.push(0x08),
+            'e' | 'E' => bytes.push(0x1B),
+            'f' => bytes.push(0x0C),
+            'n' => bytes.push(b'\n'),
+            'r' => bytes.push(b'\r'),
+            't' => bytes.push(b'\t'),
+            'v' => bytes.push(0x0B),
+            '\\' | '\'' | '"' | '?' => push_char(bytes, c),
+            '0'..='7' => {
+                let mut value = c.to_digit(8).unwrap();
+
+                for _ in 1..3 {
+                    match self.next_digit(8) {
+                        Some(d) => value = value * 8 + d,
+                        None => break,
+                    }
+                }
+
+                // Like bash, an octal value larger than a byte wraps around.
+                bytes.push(value as u8);
+            },
+            'x' => match self.read_hex(2) {
+                Some(value) => bytes.push(value as u8),
+                None => bytes.extend_from_slice(b"\\x"),
+            },
+            'u' | 'U' => {
+                let max_digits = if c == 'u' { 4 } else { 8 };
+
+                match self.read_hex(max_digits) {
+                    Some(value) => push_char(bytes, char::from_u32(value).unwrap_or('\u{FFFD}')),
+                    None => {
+                        bytes.push(b'\\');
+                        push_char(bytes, c);
+                    },
+                }
+            },
+            // The closing quote is never a control character, so `$'\c'` keeps the backslash and the `c`.
+            'c' => match self.chars.next_if(|&c| c != '\'') {
+                Some(c) if c.is_ascii() => bytes.push(c.to_ascii_uppercase() as u8 ^ 0x40),
+                Some(c) => {
+                    bytes.extend_from_slice(b"\\c");
+                    push_char(bytes, c);
+                },
+                None => bytes.extend_from_slice(b"\\c"),
+            },
+            _ => {
+                // Unknown escape sequences are kept as they are.
+                bytes.push(b'\\');
+                push_char(bytes, c);
+            },
+        }
+    }
+
+    #[inline]
+    fn next_digit(&mut self, radix: u32) -> Option<u32> {
+        self.chars.next_if(|c| c.is_digit(radix)).and_then(|c| c.to_digit(radix))
+    }
+
+    /// Read one to `max_digits` hexadecimal digits.
+    fn read_hex(&mut self, max_digits: usize) -> Option<u32> {
+        let mut value = self.next_digit(16)?;
+
+        for _ in 1..max_digits {
+            match self.next_digit(16) {
+                Some(d) => value = value * 16 + d,
+                None => break,
+            }
+        }
+
+        Some(value)
+    }
+}
+
+#[inline]
+fn push_char(bytes: &mut Vec<u8>, c: char) {
+    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+fn unescape_here_document(body: &str) -> String {
+    let mut result = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+
+            continue;
+        }
+
+        match chars.peek() {
+            Some('\\' | '$' | '`') => result.extend(chars.next()),
+            Some('\n') => {
+                chars.next();
+            },
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
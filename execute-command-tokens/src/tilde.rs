@@ -0,0 +1,131 @@
+use std::{env, path::PathBuf};
+
+use crate::Token;
+
+/// A source of home directories for tilde expansion.
+///
+/// It is implemented for closures taking the user name (`None` for the current user), so a fixed mapping can be used in tests.
+pub trait HomeDirs {
+    /// Get the home directory of `user`, or of the current user if `user` is `None`.
+    fn home_dir(&self, user: Option<&str>) -> Option<PathBuf>;
+}
+
+impl<F: Fn(Option<&str>) -> Option<PathBuf>> HomeDirs for F {
+    #[inline]
+    fn home_dir(&self, user: Option<&str>) -> Option<PathBuf> {
+        self(user)
+    }
+}
+
+/// The home directories of the running system.
+///
+/// The home directory of the current user comes from the `HOME` environment variable (`USERPROFILE` on Windows). The home directories of other users are looked up in the user database with `getpwnam_r` on Unix, so NSS sources such as LDAP are also used, and are not available on other platforms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SystemHomeDirs;
+
+impl HomeDirs for SystemHomeDirs {
+    fn home_dir(&self, user: Option<&str>) -> Option<PathBuf> {
+        match user {
+            None => {
+                let home = env::var_os("HOME").filter(|home| !home.is_empty());
+
+                #[cfg(windows)]
+                let home =
+                    home.or_else(|| env::var_os("USERPROFILE").filter(|home| !home.is_empty()));
+
+                home.map(PathBuf::from)
+            },
+            #[cfg(unix)]
+            Some(user) => user_home_dir(user),
+            #[cfg(not(unix))]
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    use std::{
+        ffi::{CStr, CString, OsStr},
+        mem::MaybeUninit,
+        os::unix::ffi::OsStrExt,
+        ptr,
+    };
+
+    let name = CString::new(user).ok()?;
+
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+    loop {
+        let mut passwd = MaybeUninit::<libc::passwd>::uninit();
+        let mut result = ptr::null_mut();
+
+        // SAFETY: `name` is a C string, and `passwd` and `buffer` outlive the call with the given sizes.
+        let code = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                passwd.as_mut_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        match code {
+            0 if !result.is_null() => {
+                // SAFETY: `result` points to `passwd`, whose strings are stored in `buffer`.
+                let home = unsafe { CStr::from_ptr((*result).pw_dir) };
+
+                return Some(PathBuf::from(OsStr::from_bytes(home.to_bytes())));
+            },
+            libc::ERANGE if buffer.len() < 1 << 20 => buffer.resize(buffer.len() * 2, 0),
+            libc::EINTR => (),
+            _ => return None,
+        }
+    }
+}
+
+/// Expand a leading unquoted `~` or `~user` of each token into the home directory given by `home_dirs`.
+///
+/// The tilde prefix lasts until the first `/`. It is kept as it is if any of its characters is quoted, or if the home directory is unknown or not valid UTF-8. The expanded home directory is treated as quoted text, so it is not subject to glob expansion.
+pub fn expand_tildes<H: HomeDirs + ?Sized>(tokens: Vec<Token>, home_dirs: &H) -> Vec<Token> {
+    tokens
+        .into_iter()
+        .map(|token| expand_tilde(token, home_dirs).unwrap_or_else(|token| token))
+        .collect()
+}
+
+fn expand_tilde<H: HomeDirs + ?Sized>(token: Token, home_dirs: &H) -> Result<Token, Token> {
+    let text = token.as_str();
+
+    if !text.starts_with('~') {
+        return Err(token);
+    }
+
+    let prefix_length = text.find('/').unwrap_or(text.len());
+
+    if (0..prefix_length).any(|index| token.is_quoted_at(index)) {
+        return Err(token);
+    }
+
+    let user = &text[1..prefix_length];
+    let user = if user.is_empty() { None } else { Some(user) };
+
+    let Some(home) = home_dirs.home_dir(user) else {
+        return Err(token);
+    };
+
+    let Some(home) = home.to_str() else {
+        return Err(token);
+    };
+
+    let mut expanded = Token::default();
+
+    expanded.push_str(home, true);
+
+    for (index, c) in text.char_indices().skip_while(|(index, _)| *index < prefix_length) {
+        expanded.push(c, token.is_quoted_at(index));
+    }
+
+    Ok(expanded)
+}
@@ -0,0 +1,129 @@
+use std::{
+    ffi::OsStr,
+    fmt::{self, Display, Formatter},
+    ops::Range,
+};
+
+/// A parsed token which remembers which of its characters were quoted or escaped.
+///
+/// Expansions such as globbing only apply to the unquoted characters of a token.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Token {
+    text:   String,
+    /// Sorted and non-overlapping byte ranges of the quoted characters.
+    quoted: Vec<Range<usize>>,
+}
+
+impl Token {
+    /// Create a token whose characters are all unquoted.
+    #[inline]
+    pub fn unquoted<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(), quoted: Vec::new()
+        }
+    }
+
+    /// Create a token whose characters are all quoted.
+    #[inline]
+    pub fn quoted<S: Into<String>>(text: S) -> Self {
+        let mut token = Self::default();
+
+        token.push_str(&text.into(), true);
+
+        token
+    }
+
+    /// Get the text of this token with quotes and escapes removed.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// Get the text of this token with quotes and escapes removed.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Check whether the character at the byte `index` was quoted or escaped.
+    #[inline]
+    pub fn is_quoted_at(&self, index: usize) -> bool {
+        self.quoted.iter().any(|range| range.contains(&index))
+    }
+
+    /// Check whether none of the characters was quoted or escaped. An empty token from `''` is not considered unquoted.
+    #[inline]
+    pub fn is_unquoted(&self) -> bool {
+        self.quoted.is_empty() && !self.text.is_empty()
+    }
+
+    /// Get the name and the value if this token is an environment variable assignment like `NAME=value`. The name and the `=` must be unquoted, and the name must consist of ASCII letters, digits and underscores without a leading digit.
+    pub fn as_assignment(&self) -> Option<(&str, &str)> {
+        let index = self.text.find('=')?;
+        let name = &self.text[..index];
+
+        let mut name_chars = name.chars();
+
+        let valid_name = name_chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name_chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if !valid_name || (0..=index).any(|index| self.is_quoted_at(index)) {
+            return None;
+        }
+
+        Some((name, &self.text[index + 1..]))
+    }
+
+    /// Iterate over the characters of this token along with whether each one was quoted or escaped.
+    #[inline]
+    pub fn quoted_chars(&self) -> impl Iterator<Item = (char, bool)> + '_ {
+        self.text.char_indices().map(|(index, c)| (c, self.is_quoted_at(index)))
+    }
+
+    pub(crate) fn push(&mut self, c: char, quoted: bool) {
+        self.push_str(c.encode_utf8(&mut [0; 4]), quoted);
+    }
+
+    pub(crate) fn push_str(&mut self, s: &str, quoted: bool) {
+        let start = self.text.len();
+
+        self.text.push_str(s);
+
+        if quoted && !s.is_empty() {
+            let end = self.text.len();
+
+            match self.quoted.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => self.quoted.push(start..end),
+            }
+        }
+    }
+}
+
+impl Display for Token {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl AsRef<str> for Token {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<OsStr> for Token {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        OsStr::new(self.as_str())
+    }
+}
+
+impl From<Token> for String {
+    #[inline]
+    fn from(token: Token) -> Self {
+        token.into_string()
+    }
+}
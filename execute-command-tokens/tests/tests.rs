@@ -1,4 +1,9 @@
-use execute_command_tokens::command_tokens;
+use std::{env, fs, path::PathBuf, process};
+
+use execute_command_tokens::{
+    NoMatchPolicy, ParseOptions, Token, command_tokens, command_tokens_with_options, expand_globs,
+    expand_tildes, parse_command, parse_script, script_tokens, split_env_assignments, tokenize,
+};
 
 #[test]
 fn v1() {
@@ -79,3 +84,422 @@ fn tolerance_v1() {
     assert_eq!(vec!["program", "1234\"  "], command_tokens("program '1234\"  "));
     assert_eq!(vec!["program", "1234'  "], command_tokens("program \"1234'  "));
 }
+
+#[test]
+fn tolerance_v2() {
+    assert_eq!(vec!["program", "ab", "c"], command_tokens("program 'a'b  c"));
+    assert_eq!(vec!["program", "", "c"], command_tokens("program ''  c"));
+    assert_eq!(vec!["program", "abc"], command_tokens(r"program abc\"));
+}
+
+#[test]
+fn empty_quotes_v1() {
+    assert_eq!(vec!["program", "", "arg1"], command_tokens("program '' arg1"));
+    assert_eq!(vec!["program", ""], command_tokens("program \"\""));
+
+    // An empty quoted part joined to a token does not leave an empty token behind.
+    assert_eq!(vec!["a", "b"], command_tokens("a'' \tb"));
+    assert_eq!(vec!["a", "a"], command_tokens("''a \ta"));
+    assert_eq!(vec!["ab#"], command_tokens("ab''\\#\n"));
+    assert_eq!(vec!["b\""], command_tokens("b\"\"\\\"\t "));
+}
+
+#[test]
+fn comments_v1() {
+    assert_eq!(vec!["program", "#", "a#b"], command_tokens("program # a#b"));
+
+    let options = ParseOptions::new().comments(true);
+
+    assert_eq!(
+        vec!["program", "arg1"],
+        command_tokens_with_options("program arg1 # note", options)
+    );
+    assert_eq!(
+        vec!["program", "arg1", "arg2"],
+        command_tokens_with_options("program arg1 # note\narg2", options)
+    );
+    assert_eq!(
+        vec!["program", "a#b", "#", "#"],
+        command_tokens_with_options(r#"program a#b '#' \#"#, options)
+    );
+    assert!(command_tokens_with_options("# program arg1", options).is_empty());
+}
+
+#[test]
+fn line_continuation_v1() {
+    assert_eq!(vec!["program", "\narg1"], command_tokens("program \\\narg1"));
+
+    let options = ParseOptions::new().line_continuation(true);
+
+    assert_eq!(vec!["program", "arg1"], command_tokens_with_options("program \\\narg1", options));
+    assert_eq!(vec!["program", "arg1"], command_tokens_with_options("program \\\r\narg1", options));
+    assert_eq!(vec!["program", "arg1"], command_tokens_with_options("program ar\\\ng1", options));
+    assert_eq!(vec!["program", "arg1"], command_tokens_with_options("program 'ar\\\ng1'", options));
+}
+
+#[test]
+fn script_v1() {
+    let script = r#"
+# The first command.
+program1 arg1 'arg 2' # trailing comment
+
+program2 \
+    --opt1 \
+    --opt2 "multi
+line"
+    # indented comment
+program3
+"#;
+
+    assert_eq!(
+        vec![
+            vec!["program1", "arg1", "arg 2"],
+            vec!["program2", "--opt1", "--opt2", "multi\nline"],
+            vec!["program3"],
+        ],
+        script_tokens(script, ParseOptions::script())
+    );
+
+    assert_eq!(
+        vec![vec!["#", "note"], vec!["program", "\n"]],
+        script_tokens("# note\nprogram \\\n", ParseOptions::new())
+    );
+}
+
+#[test]
+fn ansi_c_quoting_v1() {
+    assert_eq!(vec!["program", "$n"], command_tokens(r"program $'\n'"));
+
+    let options = ParseOptions::new().ansi_c_quoting(true);
+
+    assert_eq!(
+        vec!["program", "\n\t\r\x07\x08\x0B\x0C\\'\"?"],
+        command_tokens_with_options(r#"program $'\n\t\r\a\b\v\f\\\'\"\?'"#, options)
+    );
+    assert_eq!(
+        vec!["program", "\x1B\x1B\x1B\x1B\0A"],
+        command_tokens_with_options(r"program $'\e\E\x1b\033\0'A", options)
+    );
+    assert_eq!(
+        vec!["program", "é€😀\x01\x01"],
+        command_tokens_with_options(r"program $'\xC3\xA9€\U0001F600\cA\ca'", options)
+    );
+    assert_eq!(
+        vec!["program", "a b\nc"],
+        command_tokens_with_options(r"program a$' b\n'c", options)
+    );
+    assert_eq!(
+        vec!["program", "$'\\n'", "$", "$a"],
+        command_tokens_with_options(r#"program "$'\\n'" $ $a"#, options)
+    );
+}
+
+#[test]
+fn ansi_c_quoting_tolerance_v1() {
+    let options = ParseOptions::new().ansi_c_quoting(true);
+
+    assert_eq!(
+        vec!["program", "\\q\\x\\u\\"],
+        command_tokens_with_options(r"program $'\q\x\u\", options)
+    );
+    assert_eq!(
+        vec!["program", "\u{FFFD}"],
+        command_tokens_with_options(r"program $'\xFF'", options)
+    );
+    assert_eq!(vec!["program", "ab"], command_tokens_with_options("program $'ab", options));
+    assert_eq!(
+        vec!["program", "\\c", "\\ca", "x"],
+        command_tokens_with_options(r"program $'\c' $'\c'a x", options)
+    );
+}
+
+#[test]
+fn tokenize_v1() {
+    let tokens = tokenize(r#"program a'*'b "c" \d"#, ParseOptions::new());
+
+    assert_eq!(
+        vec!["program", "a*b", "c", "d"],
+        tokens.iter().map(Token::as_str).collect::<Vec<_>>()
+    );
+    assert!(tokens[0].is_unquoted());
+    assert_eq!(
+        vec![('a', false), ('*', true), ('b', false)],
+        tokens[1].quoted_chars().collect::<Vec<_>>()
+    );
+    assert!(tokens[2].is_quoted_at(0));
+    assert!(tokens[3].is_quoted_at(0));
+}
+
+fn create_glob_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("execute-command-tokens-{}-{name}", process::id()));
+
+    let _ = fs::remove_dir_all(&dir);
+
+    for path in ["src/nested/.hidden", "src/.cache", "target"] {
+        fs::create_dir_all(dir.join(path)).unwrap();
+    }
+
+    for path in [
+        "a.rs",
+        "b.rs",
+        "c.txt",
+        "*.rs",
+        ".env",
+        "src/lib.rs",
+        "src/nested/mod.rs",
+        "src/nested/.hidden/x.rs",
+        "src/.cache/y.rs",
+    ] {
+        fs::write(dir.join(path), "").unwrap();
+    }
+
+    dir
+}
+
+fn glob(cmd: &str, dir: &PathBuf) -> Vec<String> {
+    expand_globs(tokenize(cmd, ParseOptions::new()), dir, NoMatchPolicy::PassThrough)
+        .unwrap()
+        .into_iter()
+        .map(|s| s.into_string().unwrap())
+        .collect()
+}
+
+#[test]
+fn glob_v1() {
+    let dir = create_glob_dir("glob_v1");
+
+    assert_eq!(vec!["ls", "*.rs", "a.rs", "b.rs"], glob("ls *.rs", &dir));
+    assert_eq!(vec!["ls", "*.rs", "a.rs", "b.rs", "c.txt"], glob("ls ?.*", &dir));
+    assert_eq!(vec!["ls", "a.rs", "b.rs"], glob("ls [ab].rs", &dir));
+    assert_eq!(vec!["ls", "*.rs", "a.rs", "c.txt"], glob("ls [!b].*", &dir));
+    assert_eq!(vec!["ls", "b.rs", "c.txt"], glob("ls [b-c].*", &dir));
+    assert_eq!(vec!["ls", ".env"], glob("ls .e*", &dir));
+    assert_eq!(vec!["ls", "src/", "target/"], glob("ls */", &dir));
+    assert_eq!(vec!["ls", "src/lib.rs"], glob("ls s*/lib.rs", &dir));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn glob_v2() {
+    let dir = create_glob_dir("glob_v2");
+
+    assert_eq!(vec!["ls", "*.rs"], glob("ls '*.rs'", &dir));
+    assert_eq!(vec!["ls", "*.rs"], glob(r"ls \*.rs", &dir));
+    assert_eq!(vec!["ls", "*.rs"], glob("ls '*'.rs", &dir));
+    assert_eq!(vec!["ls", "[ab].rs"], glob("ls \"[ab]\".rs", &dir));
+    assert_eq!(vec!["ls", "b.rs"], glob("ls 'b'*.rs", &dir));
+    assert_eq!(vec!["ls", "c.txt"], glob("ls c.txt", &dir));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn glob_v3() {
+    let dir = create_glob_dir("glob_v3");
+
+    assert_eq!(
+        vec!["ls", "*.rs", "a.rs", "b.rs", "src/lib.rs", "src/nested/mod.rs"],
+        glob("ls **/*.rs", &dir)
+    );
+    assert_eq!(vec!["ls", "src/lib.rs", "src/nested/mod.rs"], glob("ls src/**/*.rs", &dir));
+    assert_eq!(
+        vec!["ls", "src/lib.rs", "src/nested", "src/nested/mod.rs"],
+        glob("ls src/**", &dir)
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn glob_backtracking_v1() {
+    let dir = create_glob_dir("glob_backtracking_v1");
+
+    let name = "a".repeat(100);
+
+    fs::write(dir.join(&name), "").unwrap();
+
+    assert_eq!(vec!["ls", "a*a*a*a*a*a*a*a*b"], glob("ls a*a*a*a*a*a*a*a*b", &dir));
+    assert_eq!(vec!["ls", name.as_str()], glob("ls a*a*a*a*a*a*a*a*a", &dir));
+    assert_eq!(vec!["ls", "a.rs"], glob("ls *a*.*s", &dir));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn glob_no_match_v1() {
+    let dir = create_glob_dir("glob_no_match_v1");
+
+    assert_eq!(vec!["ls", "*.md", "[a"], glob("ls *.md [a", &dir));
+
+    let error = expand_globs(tokenize("ls *.md", ParseOptions::new()), &dir, NoMatchPolicy::Error)
+        .unwrap_err();
+
+    assert_eq!("*.md", error.pattern());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+fn home_dirs(user: Option<&str>) -> Option<PathBuf> {
+    match user {
+        None => Some(PathBuf::from("/home/me")),
+        Some("alice") => Some(PathBuf::from("/home/alice")),
+        Some(_) => None,
+    }
+}
+
+fn tilde(cmd: &str) -> Vec<String> {
+    expand_tildes(tokenize(cmd, ParseOptions::new()), &home_dirs)
+        .into_iter()
+        .map(Token::into_string)
+        .collect()
+}
+
+#[test]
+fn tilde_v1() {
+    assert_eq!(vec!["/home/me/bin/tool", "/home/me"], tilde("~/bin/tool ~"));
+    assert_eq!(vec!["/home/alice/.tool.toml", "/home/alice"], tilde("~alice/.tool.toml ~alice"));
+    assert_eq!(vec!["~bob/x", "a~/x", "--config=~/x"], tilde("~bob/x a~/x --config=~/x"));
+    assert_eq!(
+        vec!["~/x", "~/x", "~alice", "/home/me/a b"],
+        tilde(r#"'~'/x \~/x ~'alice' ~/"a b""#)
+    );
+}
+
+#[test]
+fn tilde_v2() {
+    let tokens = expand_tildes(tokenize("~/*.rs", ParseOptions::new()), &home_dirs);
+
+    assert!(tokens[0].is_quoted_at(0));
+    assert!(!tokens[0].is_quoted_at("/home/me/".len()));
+
+    assert_eq!(vec!["~"], command_tokens("~"));
+    assert_ne!(
+        vec!["~"],
+        command_tokens_with_options("~", ParseOptions::new().tilde_expansion(true))
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn system_home_dirs_v1() {
+    let options = ParseOptions::new().tilde_expansion(true);
+
+    assert_eq!(vec!["/root/x"], command_tokens_with_options("~root/x", options));
+    assert_eq!(
+        vec!["~no-such-user-of-execute/x"],
+        command_tokens_with_options("~no-such-user-of-execute/x", options)
+    );
+}
+
+#[test]
+fn env_assignments_v1() {
+    let (envs, tokens) = split_env_assignments(tokenize(
+        r#"A=1 _B="2 3" C= program D=4 --e=5"#,
+        ParseOptions::new(),
+    ));
+
+    assert_eq!(
+        vec![("A", "1"), ("_B", "2 3"), ("C", "")],
+        envs.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["program", "D=4", "--e=5"],
+        tokens.iter().map(Token::as_str).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn env_assignments_v2() {
+    for cmd in [
+        "'A=1' program",
+        "A'='1 program",
+        "\\A=1 program",
+        "1A=1 program",
+        "A-B=1 program",
+        "=1 program",
+    ] {
+        let (envs, tokens) = split_env_assignments(tokenize(cmd, ParseOptions::new()));
+
+        assert!(envs.is_empty(), "{cmd}");
+        assert_eq!(2, tokens.len(), "{cmd}");
+    }
+}
+
+fn parse_here(cmd: &str) -> (Vec<String>, Option<String>) {
+    let command = parse_command(cmd, ParseOptions::new().here_documents(true));
+
+    (command.tokens.into_iter().map(Token::into_string).collect(), command.input)
+}
+
+#[test]
+fn here_string_v1() {
+    assert_eq!(vec!["bc", "<<<", "1"], command_tokens("bc <<< 1"));
+
+    assert_eq!((vec!["bc".to_string()], Some("1 + 1\n".to_string())), parse_here("bc <<< '1 + 1'"));
+    assert_eq!(
+        (vec!["bc".to_string(), "-q".to_string()], Some("2\n".to_string())),
+        parse_here("bc<<<2 -q")
+    );
+    assert_eq!((vec!["bc".to_string()], Some("\n".to_string())), parse_here("bc <<<"));
+    assert_eq!((vec!["bc".to_string(), "<<<".to_string()], None), parse_here("bc '<<<'"));
+}
+
+#[test]
+fn here_document_v1() {
+    let body = "SELECT '\\$1', \\\\, \\x;\na \\\nb\n";
+
+    let (tokens, input) = parse_here(&format!("psql -d app <<EOF --quiet\n{body}EOF\n"));
+
+    assert_eq!(vec!["psql", "-d", "app", "--quiet"], tokens);
+    assert_eq!(Some("SELECT '$1', \\, \\x;\na b\n".to_string()), input);
+
+    let (_, input) = parse_here(&format!("psql <<'EOF'\n{body}EOF"));
+
+    assert_eq!(Some(body.to_string()), input);
+
+    let (_, input) = parse_here(&format!("psql <<\"E\"OF\n{body}EOF"));
+
+    assert_eq!(Some(body.to_string()), input);
+
+    let (_, input) = parse_here(&format!("psql <<\\EOF\n{body}EOF"));
+
+    assert_eq!(Some(body.to_string()), input);
+}
+
+#[test]
+fn here_document_v2() {
+    let (tokens, input) = parse_here("cat <<-END\n\t\tindented\n\tEND\n");
+
+    assert_eq!(vec!["cat"], tokens);
+    assert_eq!(Some("indented\n".to_string()), input);
+
+    let (_, input) = parse_here("cat <<END\nunterminated");
+
+    assert_eq!(Some("unterminated".to_string()), input);
+
+    let (_, input) = parse_here("cat <<END");
+
+    assert_eq!(Some(String::new()), input);
+
+    let (_, input) = parse_here("cat <<A <<B\na\nA\nb\nB\n");
+
+    assert_eq!(Some("b\n".to_string()), input);
+}
+
+#[test]
+fn here_document_script_v1() {
+    let script = "# migrations\npsql <<'SQL'\nCREATE TABLE t (id int);\n\n# not a \
+                  comment\nSQL\nprogram arg1\n";
+
+    let commands = parse_script(script, ParseOptions::script().here_documents(true));
+
+    assert_eq!(2, commands.len());
+    assert_eq!(vec!["psql"], commands[0].tokens.iter().map(Token::as_str).collect::<Vec<_>>());
+    assert_eq!(Some("CREATE TABLE t (id int);\n\n# not a comment\n"), commands[0].input.as_deref());
+    assert_eq!(
+        vec!["program", "arg1"],
+        commands[1].tokens.iter().map(Token::as_str).collect::<Vec<_>>()
+    );
+    assert_eq!(None, commands[1].input);
+}
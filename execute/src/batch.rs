@@ -0,0 +1,308 @@
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(not(windows))]
+use std::{collections::BTreeMap, env, mem};
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, ErrorKind},
+    ops::Range,
+    process::{Command, ExitStatus, Stdio},
+};
+
+use crate::{Job, JobOrder, JobPool};
+
+/// The space left for anything the estimate misses, as `xargs` does.
+const HEADROOM: usize = 2048;
+
+/// Options of the `execute_batches` method and function.
+///
+/// By default, the size of the arguments of each batch is limited by `ARG_MAX` of the system, or by the maximum length of a command line on Windows, the number of arguments is not limited, and one batch runs at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BatchOptions {
+    arg_limit:   Option<usize>,
+    max_args:    Option<usize>,
+    concurrency: usize,
+}
+
+impl BatchOptions {
+    /// Create the default options.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            arg_limit: None, max_args: None, concurrency: 1
+        }
+    }
+
+    /// Set the maximum size of the added arguments of each batch, instead of deriving it from the limit of the system and the base command.
+    ///
+    /// On Unix, the size is in bytes, and each argument takes its length, plus one for its nul terminator and the size of a pointer for its entry in `argv`. The default limit is `ARG_MAX` less the size of the environment.
+    ///
+    /// On Windows, the size is in UTF-16 units, and each argument takes its length on the command line, quoted the way the standard library does, plus one for the space before it. The default limit is the 32767 units of the command line, which does not include the environment.
+    #[inline]
+    pub const fn arg_limit(mut self, size: usize) -> Self {
+        self.arg_limit = Some(size);
+
+        self
+    }
+
+    /// Set the maximum number of added arguments of each batch, like `xargs -n`.
+    #[inline]
+    pub const fn max_args(mut self, max_args: usize) -> Self {
+        self.max_args = Some(max_args);
+
+        self
+    }
+
+    /// Set how many batches can run at once, like `xargs -P`. Zero is treated as one.
+    #[inline]
+    pub const fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = if concurrency == 0 { 1 } else { concurrency };
+
+        self
+    }
+}
+
+impl Default for BatchOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The result of a batch of the `execute_batches` method and function.
+#[derive(Debug)]
+pub struct BatchReport {
+    /// The positions of the arguments of this batch in the given arguments.
+    pub arguments: Range<usize>,
+    /// The exit status of the batch, or the error which happened while spawning or waiting for it.
+    pub result:    Result<ExitStatus, io::Error>,
+}
+
+impl BatchReport {
+    /// Whether the batch exited with status code 0.
+    #[inline]
+    pub fn success(&self) -> bool {
+        matches!(&self.result, Ok(status) if status.success())
+    }
+}
+
+#[cfg(not(windows))]
+#[inline]
+fn arg_size<S: AsRef<OsStr>>(arg: S) -> usize {
+    arg.as_ref().len() + 1 + mem::size_of::<usize>()
+}
+
+/// The size of an argument on a command line of Windows, in UTF-16 units. The argument is quoted if it is empty or contains a space or tab, and backslashes are escaped where they come before a quote, as `Command` does.
+#[cfg(windows)]
+fn arg_size<S: AsRef<OsStr>>(arg: S) -> usize {
+    let arg = arg.as_ref();
+
+    let quote =
+        arg.is_empty() || arg.encode_wide().any(|c| c == u16::from(b' ') || c == u16::from(b'\t'));
+
+    // The space before the argument.
+    let mut size = 1;
+    let mut backslashes = 0;
+
+    for c in arg.encode_wide() {
+        if c == u16::from(b'\\') {
+            backslashes += 1;
+        } else {
+            if c == u16::from(b'"') {
+                size += backslashes + 1;
+            }
+
+            backslashes = 0;
+        }
+
+        size += 1;
+    }
+
+    if quote {
+        size += backslashes + 2;
+    }
+
+    size
+}
+
+#[cfg(unix)]
+fn system_arg_limit() -> usize {
+    // SAFETY: `sysconf` has no memory safety requirements.
+    match unsafe { libc::sysconf(libc::_SC_ARG_MAX) } {
+        // `_POSIX_ARG_MAX`, the minimum every system supports.
+        limit if limit <= 0 => 4096,
+        limit => limit as usize,
+    }
+}
+
+/// The maximum length of a command line on Windows.
+#[cfg(not(unix))]
+fn system_arg_limit() -> usize {
+    32767
+}
+
+/// The maximum size of a single argument including its nul terminator, `MAX_ARG_STRLEN`, which is 32 pages on Linux regardless of `ARG_MAX`.
+#[cfg(target_os = "linux")]
+fn max_arg_strlen() -> usize {
+    // SAFETY: `sysconf` has no memory safety requirements.
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        page_size if page_size <= 0 => 4096,
+        page_size => page_size as usize,
+    };
+
+    32 * page_size
+}
+
+/// The size of the arguments of each batch which fits in `ARG_MAX` together with the environment of `base` and its own arguments.
+#[cfg(not(windows))]
+fn default_arg_limit(base: &Command) -> usize {
+    let mut envs: BTreeMap<OsString, OsString> = env::vars_os().collect();
+
+    for (key, value) in base.get_envs() {
+        match value {
+            Some(value) => envs.insert(key.to_os_string(), value.to_os_string()),
+            None => envs.remove(key),
+        };
+    }
+
+    let env_size: usize =
+        envs.iter().map(|(key, value)| key.len() + value.len() + 2).sum::<usize>()
+            + envs.len() * mem::size_of::<usize>();
+
+    let base_size: usize =
+        arg_size(base.get_program()) + base.get_args().map(arg_size).sum::<usize>();
+
+    // The null pointers which end `argv` and `envp`.
+    let terminators = 2 * mem::size_of::<usize>();
+
+    system_arg_limit().saturating_sub(env_size + base_size + terminators + HEADROOM)
+}
+
+/// The size of the arguments of each batch which fits in a command line together with the program and arguments of `base`. The environment is passed separately on Windows.
+#[cfg(windows)]
+fn default_arg_limit(base: &Command) -> usize {
+    // The program is always quoted, and has no space before it.
+    let program_size = base.get_program().encode_wide().count() + 2;
+
+    let base_size: usize = program_size + base.get_args().map(arg_size).sum::<usize>();
+
+    // The nul which ends the command line.
+    let terminator = 1;
+
+    system_arg_limit().saturating_sub(base_size + terminator + HEADROOM)
+}
+
+/// Split `args` into consecutive batches whose size is at most `arg_limit` and whose length is at most `max_args`.
+fn split(
+    args: &[OsString],
+    arg_limit: usize,
+    max_args: Option<usize>,
+) -> Result<Vec<Range<usize>>, io::Error> {
+    #[cfg(target_os = "linux")]
+    let max_arg_strlen = max_arg_strlen();
+
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+
+    for (index, arg) in args.iter().enumerate() {
+        #[cfg(target_os = "linux")]
+        if arg.len() + 1 > max_arg_strlen {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("the argument {arg:?} is longer than the limit of a single argument"),
+            ));
+        }
+
+        let arg_size = arg_size(arg);
+
+        if arg_size > arg_limit {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("the argument {arg:?} is too long to fit in a command line"),
+            ));
+        }
+
+        if size + arg_size > arg_limit || max_args.is_some_and(|max_args| index - start >= max_args)
+        {
+            batches.push(start..index);
+
+            start = index;
+            size = 0;
+        }
+
+        size += arg_size;
+    }
+
+    if start < args.len() {
+        batches.push(start..args.len());
+    }
+
+    Ok(batches)
+}
+
+/// Create a copy of `base`. The stdio and the platform-specific settings of `base` cannot be read, so they are not copied.
+pub(crate) fn copy_command(base: &Command) -> Command {
+    let mut command = Command::new(base.get_program());
+
+    command.args(base.get_args());
+
+    for (key, value) in base.get_envs() {
+        match value {
+            Some(value) => command.env(key, value),
+            None => command.env_remove(key),
+        };
+    }
+
+    if let Some(dir) = base.get_current_dir() {
+        command.current_dir(dir);
+    }
+
+    command
+}
+
+/// Execute the commands created by `factory` with `args` appended in batches, like `xargs`, so that every command line stays under the limit of the system, which is `ARG_MAX` on Unix, taking the environment into account, and the maximum length of a command line on Windows. Unlike the [`execute_batches`](crate::Execute::execute_batches) method, every setting of the commands is kept, such as their stdout and stderr, process group and resource limits, except that the stdin of every batch will be set to `Stdio::null()`. The first command is also used to derive the size of the arguments of each batch. Returns the result of every batch in order, or an error of kind `InvalidInput` without running anything if an argument cannot fit in a command line.
+pub fn execute_batches<F, I, S>(
+    mut factory: F,
+    args: I,
+    options: BatchOptions,
+) -> Result<Vec<BatchReport>, io::Error>
+where
+    F: FnMut() -> Command,
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>, {
+    let args: Vec<OsString> = args.into_iter().map(|arg| arg.as_ref().to_os_string()).collect();
+
+    let probe = factory();
+
+    let arg_limit = options.arg_limit.unwrap_or_else(|| default_arg_limit(&probe));
+
+    let batches = split(&args, arg_limit, options.max_args.map(|max_args| max_args.max(1)))?;
+
+    let mut pool = JobPool::new();
+
+    pool.set_concurrency(options.concurrency);
+    pool.set_order(JobOrder::Input);
+
+    let mut probe = Some(probe);
+
+    let jobs = batches.iter().map(|range| {
+        let mut command = probe.take().unwrap_or_else(&mut factory);
+
+        command.args(&args[range.clone()]);
+
+        // Batches which run at once cannot share the input, as `xargs` does not.
+        command.stdin(Stdio::null());
+
+        Job::new(command)
+    });
+
+    Ok(pool
+        .run(jobs)
+        .zip(batches.iter().cloned())
+        .map(|(report, arguments)| BatchReport {
+            arguments,
+            result: report.result.map(|output| output.status),
+        })
+        .collect())
+}
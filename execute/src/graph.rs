@@ -0,0 +1,313 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    io,
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    process::{Command, ExitStatus, Output},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Execute, ProcessStatus, TeeOutput, Transcript};
+
+/// A value returned by a task of a [`TaskGraph`], which tells whether the task succeeded.
+pub trait TaskOutput {
+    /// Whether the task succeeded, such as by exiting with status code 0.
+    fn success(&self) -> bool;
+}
+
+impl TaskOutput for () {
+    #[inline]
+    fn success(&self) -> bool {
+        true
+    }
+}
+
+impl TaskOutput for Option<i32> {
+    #[inline]
+    fn success(&self) -> bool {
+        *self == Some(0)
+    }
+}
+
+impl TaskOutput for ExitStatus {
+    #[inline]
+    fn success(&self) -> bool {
+        ExitStatus::success(self)
+    }
+}
+
+impl TaskOutput for ProcessStatus {
+    #[inline]
+    fn success(&self) -> bool {
+        ProcessStatus::success(self)
+    }
+}
+
+impl TaskOutput for Output {
+    #[inline]
+    fn success(&self) -> bool {
+        self.status.success()
+    }
+}
+
+impl TaskOutput for Transcript {
+    #[inline]
+    fn success(&self) -> bool {
+        self.status.success()
+    }
+}
+
+impl TaskOutput for TeeOutput {
+    #[inline]
+    fn success(&self) -> bool {
+        self.status.success() && self.branches.iter().all(|branch| branch.status.success())
+    }
+}
+
+/// What a [`TaskGraph`] does after a task fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FailurePolicy {
+    /// Start no more tasks, like `make`. The tasks which are already running are finished.
+    #[default]
+    FailFast,
+    /// Keep running every task which does not depend on a failed task, like `make -k`.
+    KeepGoing,
+}
+
+/// The identifier of a task in a [`TaskGraph`], used to declare the dependencies of other tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(usize);
+
+/// How a task of a [`TaskGraph`] ended.
+#[derive(Debug)]
+pub enum TaskOutcome<T> {
+    /// The task ran and returned a value, which may still report a failure, such as a non-zero exit status.
+    Completed(T),
+    /// The task returned an error, or panicked.
+    Error(io::Error),
+    /// The task was not run, because one of its dependencies failed, or another task failed with [`FailurePolicy::FailFast`].
+    Skipped,
+}
+
+/// The result of a task of a [`TaskGraph`].
+#[derive(Debug)]
+pub struct TaskReport<T> {
+    /// The name of the task.
+    pub name:    String,
+    /// How the task ended.
+    pub outcome: TaskOutcome<T>,
+    /// How long the task ran. It is zero if the task was skipped.
+    pub elapsed: Duration,
+}
+
+impl<T: TaskOutput> TaskReport<T> {
+    /// Whether the task ran and succeeded.
+    #[inline]
+    pub fn success(&self) -> bool {
+        matches!(&self.outcome, TaskOutcome::Completed(output) if output.success())
+    }
+}
+
+type TaskFunction<'a, T> = Box<dyn FnOnce() -> Result<T, io::Error> + Send + 'a>;
+
+struct Task<'a, T> {
+    name:         String,
+    dependencies: Vec<usize>,
+    run:          Option<TaskFunction<'a, T>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+/// A set of tasks with dependencies between them, such as the steps of a build, which are run in parallel like `make -j`.
+///
+/// A task is a function which can use any of the execution methods, and returns a value that implements [`TaskOutput`]. A task is started once every task it depends on has succeeded. Tasks can only depend on tasks added before them, so the graph never has cycles.
+pub struct TaskGraph<'a, T = Output> {
+    tasks:          Vec<Task<'a, T>>,
+    concurrency:    usize,
+    failure_policy: FailurePolicy,
+}
+
+impl<T> Debug for TaskGraph<'_, T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskGraph")
+            .field("tasks", &self.tasks.iter().map(|task| &task.name).collect::<Vec<_>>())
+            .field("concurrency", &self.concurrency)
+            .field("failure_policy", &self.failure_policy)
+            .finish()
+    }
+}
+
+impl<'a, T: TaskOutput + Send> TaskGraph<'a, T> {
+    /// Create an empty graph. By default, as many tasks as the available parallelism run at once, and no more tasks are started after one fails.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            tasks:          Vec::new(),
+            concurrency:    thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            failure_policy: FailurePolicy::default(),
+        }
+    }
+
+    /// Set how many tasks can run at once. Zero is treated as one.
+    #[inline]
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    /// Set what to do after a task fails.
+    #[inline]
+    pub fn set_failure_policy(&mut self, failure_policy: FailurePolicy) {
+        self.failure_policy = failure_policy;
+    }
+
+    /// Add a task, which runs after every task in `dependencies` has succeeded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a dependency is not a task of this graph.
+    pub fn add<S: Into<String>, F>(&mut self, name: S, dependencies: &[TaskId], run: F) -> TaskId
+    where
+        F: FnOnce() -> Result<T, io::Error> + Send + 'a, {
+        let dependencies = dependencies
+            .iter()
+            .map(|dependency| {
+                assert!(
+                    dependency.0 < self.tasks.len(),
+                    "the dependency is not a task of this graph"
+                );
+
+                dependency.0
+            })
+            .collect();
+
+        self.tasks.push(Task {
+            name: name.into(),
+            dependencies,
+            run: Some(Box::new(run)),
+        });
+
+        TaskId(self.tasks.len() - 1)
+    }
+
+    /// Run the tasks and wait for them to finish. Returns the result of every task in the order they were added.
+    pub fn run(mut self) -> Vec<TaskReport<T>> {
+        let mut states = vec![TaskState::Pending; self.tasks.len()];
+        let mut outcomes: Vec<Option<(TaskOutcome<T>, Duration)>> =
+            self.tasks.iter().map(|_| None).collect();
+
+        let mut running = 0;
+        let mut stopped = false;
+
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            loop {
+                // Dependencies always come before their dependents, so one pass in order settles every task which can be settled.
+                for (index, task) in self.tasks.iter_mut().enumerate() {
+                    if stopped || states[index] != TaskState::Pending {
+                        continue;
+                    }
+
+                    let mut dependency_states = task.dependencies.iter().map(|&i| states[i]);
+
+                    if dependency_states
+                        .clone()
+                        .any(|state| matches!(state, TaskState::Failed | TaskState::Skipped))
+                    {
+                        states[index] = TaskState::Skipped;
+                    } else if running < self.concurrency
+                        && dependency_states.all(|state| state == TaskState::Succeeded)
+                    {
+                        let run = task.run.take().unwrap();
+                        let sender = sender.clone();
+
+                        scope.spawn(move || {
+                            let started = Instant::now();
+
+                            let result = panic::catch_unwind(AssertUnwindSafe(run))
+                                .unwrap_or_else(|_| Err(io::Error::other("task panicked")));
+
+                            let _ = sender.send((index, result, started.elapsed()));
+                        });
+
+                        states[index] = TaskState::Running;
+                        running += 1;
+                    }
+                }
+
+                if running == 0 {
+                    break;
+                }
+
+                // Every running task sends exactly one message, and the sender of this thread is still alive.
+                let (index, result, elapsed) = receiver.recv().unwrap();
+
+                running -= 1;
+
+                let outcome = match result {
+                    Ok(output) => TaskOutcome::Completed(output),
+                    Err(err) => TaskOutcome::Error(err),
+                };
+
+                let succeeded =
+                    matches!(&outcome, TaskOutcome::Completed(output) if output.success());
+
+                states[index] = if succeeded {
+                    TaskState::Succeeded
+                } else {
+                    if self.failure_policy == FailurePolicy::FailFast {
+                        stopped = true;
+                    }
+
+                    TaskState::Failed
+                };
+
+                outcomes[index] = Some((outcome, elapsed));
+            }
+        });
+
+        self.tasks
+            .into_iter()
+            .zip(outcomes)
+            .map(|(task, outcome)| {
+                let (outcome, elapsed) = outcome.unwrap_or((TaskOutcome::Skipped, Duration::ZERO));
+
+                TaskReport {
+                    name: task.name,
+                    outcome,
+                    elapsed,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a> TaskGraph<'a, Output> {
+    /// Add a task which executes `command` with [`execute_output`](Execute::execute_output). See [`add`](Self::add).
+    #[inline]
+    pub fn add_command<S: Into<String>>(
+        &mut self,
+        name: S,
+        dependencies: &[TaskId],
+        command: &'a mut Command,
+    ) -> TaskId {
+        self.add(name, dependencies, move || command.execute_output())
+    }
+}
+
+impl<T: TaskOutput + Send> Default for TaskGraph<'_, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
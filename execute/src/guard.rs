@@ -0,0 +1,442 @@
+use std::{
+    io::{self, Read, Write},
+    ops::{Deref, DerefMut},
+    process::{Child, ExitStatus, Output},
+    thread,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    mem,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+};
+
+use crate::{
+    ProcessStatus, Termination, TerminationPolicy,
+    termination::{POLL_INTERVAL, terminate_children},
+};
+
+/// A guard of a spawned `Child`, which kills the process with `SIGKILL` and reaps it when it is dropped, including during panic unwinding, so dropping never blocks for a grace period. Call [`terminate`](Self::terminate) first to end the process gracefully according to its [`TerminationPolicy`].
+///
+/// Dropping the guard of a process which has already been waited for does nothing.
+///
+/// Use the `try_wait`, `wait` and `kill` methods of the guard instead of the ones of `Child`, because a process reaped by [`wait_with_resource_usage`](Self::wait_with_resource_usage) is unknown to the `Child`.
+#[derive(Debug)]
+pub struct ChildGuard {
+    child:              Option<Child>,
+    termination_policy: TerminationPolicy,
+    /// The status of the process if it has been reaped by `wait4` instead of the `Child`.
+    reaped:             Option<ProcessStatus>,
+    /// Shared with the kill handles, if any have been created.
+    #[cfg(unix)]
+    exit_state:         Option<Arc<ExitState>>,
+}
+
+impl ChildGuard {
+    /// Guard a spawned process.
+    #[inline]
+    pub const fn new(child: Child) -> Self {
+        Self {
+            child:                   Some(child),
+            termination_policy:      TerminationPolicy::new(),
+            reaped:                  None,
+            #[cfg(unix)]
+            exit_state:              None,
+        }
+    }
+
+    /// Set how the process is terminated by [`terminate`](Self::terminate).
+    #[inline]
+    pub fn set_termination_policy(&mut self, termination_policy: TerminationPolicy) {
+        self.termination_policy = termination_policy;
+    }
+
+    /// Terminate the process according to the termination policy and reap it. Returns which step ended the process.
+    #[inline]
+    pub fn terminate(&mut self) -> Result<Termination, io::Error> {
+        let policy = self.termination_policy;
+
+        terminate_children(&mut [self], None, policy)
+    }
+
+    /// Check whether the process has exited without blocking, like `Child::try_wait`.
+    #[inline]
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, io::Error> {
+        match self.reaped {
+            Some(status) => Ok(Some(status.exit_status())),
+            None => {
+                #[cfg(unix)]
+                if !self.prepare_reap(false)? {
+                    return Ok(None);
+                }
+
+                self.child_mut().try_wait()
+            },
+        }
+    }
+
+    /// Wait for the process to exit, like `Child::wait`.
+    #[inline]
+    pub fn wait(&mut self) -> Result<ExitStatus, io::Error> {
+        match self.reaped {
+            Some(status) => Ok(status.exit_status()),
+            None => {
+                // Like `Child::wait`, stdin is closed first, in case the process is waiting for its end.
+                drop(self.stdin.take());
+
+                #[cfg(unix)]
+                self.prepare_reap(true)?;
+
+                self.child_mut().wait()
+            },
+        }
+    }
+
+    /// Kill the process with `SIGKILL`, like `Child::kill`. Nothing happens if it has already been reaped.
+    #[inline]
+    pub fn kill(&mut self) -> Result<(), io::Error> {
+        match self.reaped {
+            Some(_) => Ok(()),
+            None => self.child_mut().kill(),
+        }
+    }
+
+    /// Wait for the process to exit and reap it with `wait4`, in order to get the resources it used. If the process has already been reaped through the `Child`, its resource usage is not available.
+    #[cfg(target_os = "linux")]
+    pub fn wait_with_resource_usage(&mut self) -> Result<ProcessStatus, io::Error> {
+        if let Some(status) = self.reaped {
+            return Ok(status);
+        }
+
+        drop(self.stdin.take());
+
+        self.prepare_reap(true)?;
+
+        match crate::resource_usage::wait4(self.id())? {
+            Some((status, resource_usage)) => {
+                let status = ProcessStatus::from(status).with_resource_usage(resource_usage);
+
+                self.reaped = Some(status);
+
+                Ok(status)
+            },
+            None => self.wait().map(ProcessStatus::from),
+        }
+    }
+
+    #[inline]
+    fn child_mut(&mut self) -> &mut Child {
+        self.child.as_mut().unwrap()
+    }
+
+    /// Get a handle which can kill the process from another thread, even while this guard is waiting for it.
+    #[cfg(unix)]
+    pub(crate) fn kill_handle(&mut self) -> Result<KillHandle, io::Error> {
+        if self.exit_state.is_none() {
+            // A process which has already been reaped must never be signaled through a handle.
+            let exited = self.try_wait()?.is_some();
+
+            self.exit_state = Some(Arc::new(ExitState {
+                exited:  Mutex::new(exited),
+                condvar: Condvar::new(),
+            }));
+        }
+
+        Ok(KillHandle {
+            pid:        self.id() as libc::pid_t,
+            exit_state: Arc::clone(self.exit_state.as_ref().unwrap()),
+        })
+    }
+
+    /// Processes cannot be signaled while another thread is waiting for them on platforms other than Unix.
+    #[cfg(not(unix))]
+    #[inline]
+    pub(crate) fn kill_handle(&mut self) -> Result<KillHandle, io::Error> {
+        Ok(KillHandle)
+    }
+
+    /// Let the kill handles know that the process has exited before it is reaped, because its PID can be reused after that. Returns whether the process has exited, after waiting for it if `block` is `true`. Without kill handles, the `Child` is left to find out by itself.
+    #[cfg(unix)]
+    fn prepare_reap(&mut self, block: bool) -> Result<bool, io::Error> {
+        let Some(exit_state) = &self.exit_state else {
+            return Ok(true);
+        };
+
+        if *exit_state.lock() {
+            return Ok(true);
+        }
+
+        // Only the guard reaps the process, so it cannot be reaped while this thread waits without holding the lock.
+        let exited = peek_exit(self.child.as_ref().unwrap().id(), block)?;
+
+        if exited {
+            *exit_state.lock() = true;
+
+            exit_state.condvar.notify_all();
+        }
+
+        Ok(exited)
+    }
+
+    /// Wait for the process to exit for at most `timeout`. Returns `None` if it is still running, for example, to [`terminate`](Self::terminate) it.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>, io::Error> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Release the process from this guard, so it will no longer be killed when dropped. The returned `Child` must not be waited for or killed if the process has been reaped by [`wait_with_resource_usage`](Self::wait_with_resource_usage).
+    #[inline]
+    pub fn into_inner(mut self) -> Child {
+        // The released process may be reaped at any time, so the kill handles must no longer signal it.
+        #[cfg(unix)]
+        if let Some(exit_state) = &self.exit_state {
+            *exit_state.lock() = true;
+        }
+
+        self.child.take().unwrap()
+    }
+
+    /// Wait for the process to exit and collect its stdout and stderr, like `Child::wait_with_output`, but keep the process guarded while waiting.
+    pub fn wait_with_output(&mut self) -> Result<Output, io::Error> {
+        let (stdout, stderr) = self.read_output();
+
+        let status = self.wait()?;
+
+        Ok(Output {
+            status,
+            stdout: stdout?,
+            stderr: stderr?,
+        })
+    }
+
+    /// Wait for the process to exit and collect its stdout and stderr like [`wait_with_output`](Self::wait_with_output), but reap it with `wait4` like [`wait_with_resource_usage`](Self::wait_with_resource_usage). Returns the output along with the status, which has the resources used by the process.
+    #[cfg(target_os = "linux")]
+    pub fn wait_with_output_and_resource_usage(
+        &mut self,
+    ) -> Result<(Output, ProcessStatus), io::Error> {
+        let (stdout, stderr) = self.read_output();
+
+        let status = self.wait_with_resource_usage()?;
+
+        let output = Output {
+            status: status.exit_status(), stdout: stdout?, stderr: stderr?
+        };
+
+        Ok((output, status))
+    }
+
+    /// Close stdin, and read stdout and stderr until they end.
+    pub(crate) fn read_output(
+        &mut self,
+    ) -> (Result<Vec<u8>, io::Error>, Result<Vec<u8>, io::Error>) {
+        drop(self.stdin.take());
+
+        let stdout = self.stdout.take();
+        let stderr = self.stderr.take();
+
+        thread::scope(|scope| {
+            let stderr_handle = scope.spawn(|| read_to_end(stderr));
+            let stdout = read_to_end(stdout);
+            let stderr = match stderr_handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(io::Error::other("child stderr reading thread panicked")),
+            };
+
+            (stdout, stderr)
+        })
+    }
+
+    /// Wait for the process to exit while copying its stdout into `writer` and collecting its stderr. The returned `Output::stdout` is empty.
+    ///
+    /// The stdout is copied through a small buffer, so the process is blocked by the pipe while `writer` is slow, and the memory used stays bounded. If writing fails, the stdout is closed, which usually makes the process exit with `SIGPIPE`, and the error is returned after the process is reaped.
+    pub fn wait_with_output_to_writer(
+        &mut self,
+        writer: &mut dyn Write,
+    ) -> Result<Output, io::Error> {
+        drop(self.stdin.take());
+
+        let stdout = self.stdout.take();
+        let stderr = self.stderr.take();
+
+        let (copy_result, stderr) = thread::scope(|scope| {
+            let stderr_handle = scope.spawn(|| read_to_end(stderr));
+            let copy_result = match stdout {
+                Some(mut stdout) => io::copy(&mut stdout, writer).and_then(|_| writer.flush()),
+                None => Ok(()),
+            };
+            let stderr = match stderr_handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(io::Error::other("child stderr reading thread panicked")),
+            };
+
+            (copy_result, stderr)
+        });
+
+        let status = self.wait()?;
+
+        copy_result?;
+
+        Ok(Output {
+            status,
+            stdout: Vec::new(),
+            stderr: stderr?,
+        })
+    }
+}
+
+/// Whether the process has exited, which is set before it is reaped.
+#[cfg(unix)]
+#[derive(Debug)]
+struct ExitState {
+    exited:  Mutex<bool>,
+    condvar: Condvar,
+}
+
+#[cfg(unix)]
+impl ExitState {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, bool> {
+        self.exited.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A handle which kills a guarded process from another thread. The process is no longer signaled once it has exited, so its PID cannot have been reused by another process.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct KillHandle {
+    pid:        libc::pid_t,
+    exit_state: Arc<ExitState>,
+}
+
+#[cfg(unix)]
+impl KillHandle {
+    /// Send `signal` to the process, unless it has exited.
+    pub(crate) fn signal(&self, signal: i32) -> Result<(), io::Error> {
+        let exited = self.exit_state.lock();
+
+        if *exited {
+            return Ok(());
+        }
+
+        // SAFETY: `kill` has no memory safety requirements. The process cannot be reaped while the lock is held, so its PID cannot have been reused.
+        if unsafe { libc::kill(self.pid, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Kill the process with `SIGKILL`, unless it has exited.
+    #[inline]
+    pub(crate) fn kill(&self) -> Result<(), io::Error> {
+        self.signal(libc::SIGKILL)
+    }
+
+    /// Wait for the process to exit for at most `timeout`, which is noticed when the guard waits for it. Returns whether it has exited.
+    pub(crate) fn wait_exited(&self, timeout: Duration) -> bool {
+        let exited = self.exit_state.lock();
+
+        let (exited, _) = self
+            .exit_state
+            .condvar
+            .wait_timeout_while(exited, timeout, |exited| !*exited)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        *exited
+    }
+}
+
+/// Processes cannot be signaled while another thread is waiting for them on platforms other than Unix, so this handle does nothing.
+#[cfg(not(unix))]
+#[derive(Debug, Clone)]
+pub(crate) struct KillHandle;
+
+#[cfg(not(unix))]
+impl KillHandle {
+    #[inline]
+    pub(crate) fn kill(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+/// Check whether a child process has exited, without reaping it. Waits for it to exit if `block` is `true`.
+#[cfg(unix)]
+fn peek_exit(pid: u32, block: bool) -> Result<bool, io::Error> {
+    let options = libc::WEXITED | libc::WNOWAIT | if block { 0 } else { libc::WNOHANG };
+
+    loop {
+        // SAFETY: `siginfo_t` is plain data, for which all zeros is a valid value.
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+
+        // SAFETY: `info` is valid for writes.
+        if unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, options) } == 0 {
+            // `si_signo` stays zero if the process has not exited yet.
+            return Ok(info.si_signo != 0);
+        }
+
+        let err = io::Error::last_os_error();
+
+        match err.raw_os_error() {
+            Some(libc::EINTR) => (),
+            // The process cannot be waited for, which the `Child` reports when it tries.
+            Some(libc::ECHILD) => return Ok(true),
+            _ => return Err(err),
+        }
+    }
+}
+
+pub(crate) fn read_to_end<R: Read>(reader: Option<R>) -> Result<Vec<u8>, io::Error> {
+    let mut buffer = Vec::new();
+
+    if let Some(mut reader) = reader {
+        reader.read_to_end(&mut buffer)?;
+    }
+
+    Ok(buffer)
+}
+
+impl From<Child> for ChildGuard {
+    #[inline]
+    fn from(child: Child) -> Self {
+        Self::new(child)
+    }
+}
+
+impl Deref for ChildGuard {
+    type Target = Child;
+
+    #[inline]
+    fn deref(&self) -> &Child {
+        self.child.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ChildGuard {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Child {
+        self.child.as_mut().unwrap()
+    }
+}
+
+impl Drop for ChildGuard {
+    #[inline]
+    fn drop(&mut self) {
+        if self.child.is_some() {
+            // Nothing is signaled if the process has already been reaped.
+            let _ = terminate_children(&mut [self], None, TerminationPolicy::kill());
+        }
+    }
+}
@@ -61,6 +61,78 @@ if let Some(exit_code) = command.execute().unwrap() {
 }
 ```
 
+#### Find Out How a Process Was Terminated
+
+The `execute_status` and `execute_multiple_status` methods return a `ProcessStatus`, which also tells the signal that terminated the process and whether a core was dumped.
+
+```rust,ignore
+use std::process::Command;
+
+use execute::Execute;
+
+const FFMPEG_PATH: &str = "/path/to/ffmpeg";
+
+let mut command = Command::new(FFMPEG_PATH);
+
+command.arg("-i");
+command.arg("/path/to/media-file");
+command.arg("/path/to/output-file");
+
+let status = command.execute_status().unwrap();
+
+match status.code() {
+    Some(0) => println!("Ok."),
+    Some(_) => eprintln!("Failed."),
+    None => eprintln!("Interrupted by {:?} ({status}).", status.signal_name()),
+}
+```
+
+#### Measure Resource Usage
+
+On Linux, the `execute_with_resource_usage` and `execute_multiple_with_resource_usage` methods reap the processes with `wait4` and attach the CPU time, peak RSS, page faults and context switches of each process to its `ProcessStatus`. The `execute_output_with_resource_usage` and `execute_multiple_output_with_resource_usage` methods also return the captured output.
+
+```rust
+use std::process::Command;
+
+use execute::Execute;
+
+# #[cfg(target_os = "linux")] {
+let mut command = Command::new("true");
+
+let status = command.execute_with_resource_usage().unwrap();
+
+let resource_usage = status.resource_usage().unwrap();
+
+println!("{:?} {} bytes", resource_usage.user_time(), resource_usage.max_rss());
+# }
+```
+
+#### Limit Resources
+
+On Unix, `ResourceLimits` sets `RLIMIT_CPU`, `RLIMIT_AS`, `RLIMIT_NOFILE`, `RLIMIT_FSIZE` and `RLIMIT_NPROC` for the processes spawned by a `Command`, so it works with every `Execute` method and every stage of a pipeline. The `limit_exceeded` method of `ProcessStatus` tells whether the process was terminated for exceeding the CPU time or file size limit.
+
+```rust,ignore
+use std::process::Command;
+
+use execute::{Execute, ResourceLimits};
+
+const FFMPEG_PATH: &str = "/path/to/ffmpeg";
+
+let mut command = Command::new(FFMPEG_PATH);
+
+command.arg("-i");
+command.arg("/path/to/media-file");
+command.arg("/path/to/output-file");
+
+ResourceLimits::new().cpu_time(60).file_size(1024 * 1024 * 1024).apply(&mut command);
+
+let status = command.execute_status().unwrap();
+
+if let Some(limit_exceeded) = status.limit_exceeded() {
+    eprintln!("{limit_exceeded}");
+}
+```
+
 ### Execute and Get the Output
 
 #### Output to the Screen
@@ -125,6 +197,115 @@ println!("{}", String::from_utf8(output.stdout).unwrap());
 println!("{}", String::from_utf8(output.stderr).unwrap());
 ```
 
+#### Output to Files
+
+The `Redirect` trait sends stdout and stderr to files, truncating or appending, and reads stdin from a file. The `execute_output_merged` and `execute_multiple_output_merged` methods capture stdout and stderr together in the order they are written, like `2>&1`.
+
+```rust,ignore
+use std::process::Command;
+
+use execute::{Execute, Redirect, WriteMode};
+
+const FFMPEG_PATH: &str = "/path/to/ffmpeg";
+
+let mut command = Command::new(FFMPEG_PATH);
+
+command.arg("-i");
+command.arg("/path/to/media-file");
+command.arg("/path/to/output-file");
+
+command.stdout_stderr_file("/path/to/log-file", WriteMode::Append).unwrap();
+
+command.execute_output().unwrap();
+
+let mut command = Command::new(FFMPEG_PATH);
+
+command.arg("-version");
+
+let output = command.execute_output_merged().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+```
+
+#### Output to a Writer
+
+The `execute_output_to_writer` and `execute_multiple_output_to_writer` methods copy stdout into any `Write` while the processes run, such as a file or a hasher, instead of collecting it in memory. A slow writer blocks the processes through the pipe, so the memory used stays bounded however large the output is.
+
+```rust,ignore
+use std::fs::File;
+use std::process::{Command, Stdio};
+
+use execute::Execute;
+
+let mut pg_dump_command = Command::new("pg_dump");
+
+pg_dump_command.arg("database");
+
+let mut gzip_command = Command::new("gzip");
+
+gzip_command.stderr(Stdio::piped());
+
+let mut file = File::create("/path/to/dump.sql.gz").unwrap();
+
+let output = pg_dump_command
+    .execute_multiple_output_to_writer(&mut file, &mut [&mut gzip_command])
+    .unwrap();
+
+println!("{}", String::from_utf8(output.stderr).unwrap());
+```
+
+#### Output to a Transcript (Captured)
+
+The `execute_transcript` and `execute_multiple_transcript` methods record stdout and stderr as timestamped chunks in the order they arrive, so the interleaving of the two streams is kept. A transcript can be converted back into an `Output` with separate stdout and stderr.
+
+```rust
+use std::process::{Command, Output};
+
+use execute::{Execute, Stream};
+
+# if cfg!(target_os = "linux") {
+let mut command = Command::new("sh");
+
+command.arg("-c").arg("echo out; sleep 0.1; echo err >&2");
+
+let transcript = command.execute_transcript().unwrap();
+
+for chunk in transcript.chunks.iter() {
+    let prefix = match chunk.stream {
+        Stream::Stdout => "stdout",
+        Stream::Stderr => "stderr",
+    };
+
+    print!("[{:?}] {}: {}", chunk.elapsed, prefix, String::from_utf8_lossy(&chunk.data));
+}
+
+let output = Output::from(transcript);
+
+assert_eq!(b"out\n", output.stdout.as_slice());
+assert_eq!(b"err\n", output.stderr.as_slice());
+# }
+```
+
+#### Output to a Pseudo-terminal (Captured)
+
+On Linux, the `execute_pty_output` and `execute_pty_input_output` methods run the command attached to a new pseudo-terminal with a configurable window size, so programs which check whether their output is a terminal keep their colors and progress bars. Both stdout and stderr are captured into `output.stdout`.
+
+```rust
+use std::process::Command;
+
+use execute::{Execute, PtyOptions};
+
+# #[cfg(target_os = "linux")] {
+let mut command = Command::new("ls");
+
+command.arg("--color=auto");
+
+let output = command.execute_pty_output(PtyOptions::new().window_size(40, 120)).unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+
 ### Execute and Input Data
 
 #### Input In-memory Data
@@ -189,94 +370,111 @@ println!("{}", String::from_utf8(output.stdout).unwrap());
 # }
 ```
 
-### Execute Multiple Commands and Pipe Them Together
+To choose the buffer size at runtime, such as from configuration, use the `_with_capacity` methods, which allocate the buffer on the heap. If the reader is already buffered, the `_buf_reader` methods write straight from its buffer without another copy.
 
 ```rust
+use std::io::BufReader;
 use std::process::{Command, Stdio};
+use std::fs::File;
 
 use execute::Execute;
 
 # if cfg!(target_os = "linux") {
-let mut command1 = Command::new("echo");
-command1.arg("HELLO WORLD");
+let buffer_size = 64 * 1024;
 
-let mut command2 = Command::new("cut");
-command2.arg("-d").arg(" ").arg("-f").arg("1");
+let mut cat_command = Command::new("cat");
 
-let mut command3 = Command::new("tr");
-command3.arg("A-Z").arg("a-z");
+cat_command.stdout(Stdio::piped());
 
-command3.stdout(Stdio::piped());
+let mut file = File::open("Cargo.toml").unwrap();
 
-let output = command1.execute_multiple_output(&mut [&mut command2, &mut command3]).unwrap();
+let output =
+    cat_command.execute_input_reader_output_with_capacity(&mut file, buffer_size).unwrap();
 
-assert_eq!(b"hello\n", output.stdout.as_slice());
+println!("{}", String::from_utf8(output.stdout).unwrap());
+
+let mut reader = BufReader::with_capacity(buffer_size, File::open("Cargo.toml").unwrap());
+
+let output = cat_command.execute_input_buf_reader_output(&mut reader).unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
 # }
 ```
 
-### Run a Command String in the Current Shell
+#### Input from a File
 
-The `shell` function can be used to create a `Command` instance with a single command string instead of a program name and scattered arguments.
+The `_reader` methods copy the data through a buffer in this process. When the data is in a file, the `_input_file` methods open the file at a path and hand it to the process as its stdin instead, so the process reads the file directly.
 
 ```rust
 use std::process::{Command, Stdio};
 
-use execute::{Execute, shell};
+use execute::Execute;
 
 # if cfg!(target_os = "linux") {
-let mut command = shell("cat /proc/meminfo");
+let mut cat_command = Command::new("cat");
 
-command.stdout(Stdio::piped());
+cat_command.stdout(Stdio::piped());
 
-let output = command.execute_output().unwrap();
+let output = cat_command.execute_input_file_output("Cargo.toml").unwrap();
 
 println!("{}", String::from_utf8(output.stdout).unwrap());
 # }
 ```
 
-### Parse a Command String at Runtime
+#### Input from a Closure
 
-The `command` function can be used to create a `Command` instance with a single command string instead of a program name and scattered arguments. The difference between the `shell` function and the `command` function is that the former is interpreted by the current shell while the latter is parsed by this crate.
+The `_writer` methods take a closure which writes to stdin, for data that is generated on the fly rather than held in memory or read from somewhere. stdin is closed after the closure returns.
 
 ```rust
 use std::process::{Command, Stdio};
 
-use execute::{Execute, command};
+use execute::Execute;
 
 # if cfg!(target_os = "linux") {
-let mut command = command("cat '/proc/meminfo'");
+let mut wc_command = Command::new("wc");
 
-command.stdout(Stdio::piped());
+wc_command.arg("-l");
+wc_command.stdout(Stdio::piped());
 
-let output = command.execute_output().unwrap();
+let output = wc_command
+    .execute_input_writer_output(|stdin| {
+        for i in 0..1000 {
+            writeln!(stdin, "{},row-{}", i, i)?;
+        }
 
-println!("{}", String::from_utf8(output.stdout).unwrap());
+        Ok(())
+    })
+    .unwrap();
+
+assert_eq!("1000", String::from_utf8(output.stdout).unwrap().trim());
 # }
 ```
 
-### Parse a Command String at Compile Time
+### Interact with a Running Process
 
-The `command!` macro can be used to create a `Command` instance with a single command string literal instead of a program name and scattered arguments.
+A `Session` keeps a process alive across multiple exchanges, for programs which read their input one prompt at a time. It can use pipes or, on Linux, a pseudo-terminal.
 
 ```rust
-use std::process::{Command, Stdio};
+use std::{process::Command, time::Duration};
 
-use execute::Execute;
+use execute::Session;
 
 # if cfg!(target_os = "linux") {
-let mut command = execute::command!("cat '/proc/meminfo'");
+let mut session = Session::spawn(&mut Command::new("cat")).unwrap();
 
-command.stdout(Stdio::piped());
+session.send_line("hello").unwrap();
 
-let output = command.execute_output().unwrap();
+assert_eq!(b"hello\n", session.expect("hello\n", Duration::from_secs(5)).unwrap().as_slice());
 
-println!("{}", String::from_utf8(output.stdout).unwrap());
+session.send_line("world").unwrap();
+
+assert_eq!(b"world\n", session.read_until(b'\n', Duration::from_secs(5)).unwrap().as_slice());
+
+assert!(session.wait().unwrap().success());
 # }
 ```
 
-### Create a `Command` Instance by Providing Arguments Separately
-
-The `command_args!` macro can be used to create a `Command` instance with a program name and arguments separately. The program name and arguments can be non-literal.
+### Execute Multiple Commands and Pipe Them Together
 
 ```rust
 use std::process::{Command, Stdio};
@@ -284,391 +482,1708 @@ use std::process::{Command, Stdio};
 use execute::Execute;
 
 # if cfg!(target_os = "linux") {
-let mut command = execute::command_args!("cat", "/proc/meminfo");
+let mut command1 = Command::new("echo");
+command1.arg("HELLO WORLD");
 
-command.stdout(Stdio::piped());
+let mut command2 = Command::new("cut");
+command2.arg("-d").arg(" ").arg("-f").arg("1");
 
-let output = command.execute_output().unwrap();
+let mut command3 = Command::new("tr");
+command3.arg("A-Z").arg("a-z");
 
-println!("{}", String::from_utf8(output.stdout).unwrap());
+command3.stdout(Stdio::piped());
+
+let output = command1.execute_multiple_output(&mut [&mut command2, &mut command3]).unwrap();
+
+assert_eq!(b"hello\n", output.stdout.as_slice());
 # }
 ```
-*/
 
-#[cfg(unix)]
-use std::{env, ffi::OsString};
-use std::{
-    ffi::OsStr,
-    io::{self, ErrorKind, Read, Write},
-    process::{Child, ChildStdin, ChildStdout, Command, Output, Stdio},
-    thread,
-};
+The stdin of every command except the first one is set to the pipe from the previous process. It is reset to being inherited from the parent after spawning, since a `Command` keeps the pipe open otherwise, and the previous stdin setting of a `Command` cannot be read back.
 
-pub use execute_command_macro::{command, command_args};
-use execute_command_tokens::command_tokens;
+#### Run Rust Functions as Stages
 
-const DEFAULT_READER_BUFFER_SIZE: usize = 256;
+A stage of a pipeline can also be a Rust function, which runs on its own thread and streams the data from the previous stage to the next one, so the intermediate output is never collected in memory. Use the `execute_multiple_stages` and `execute_multiple_stages_output` methods with `Stage` values.
 
-#[inline]
-fn take_child_stdin(child: &mut Child) -> Result<ChildStdin, io::Error> {
-    child.stdin.take().ok_or_else(|| io::Error::other("child stdin was not piped"))
-}
+```rust
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
 
-#[inline]
-fn take_child_stdout(child: &mut Child) -> Result<ChildStdout, io::Error> {
-    child.stdout.take().ok_or_else(|| io::Error::other("child stdout was not piped"))
-}
+use execute::{Execute, Stage};
 
-#[inline]
-fn write_stdin<D: ?Sized + AsRef<[u8]>>(mut stdin: ChildStdin, data: &D) -> Result<(), io::Error> {
-    stdin.write_all(data.as_ref())
-}
+# if cfg!(target_os = "linux") {
+let mut command1 = Command::new("printf");
+command1.arg("apple\nbanana\ncherry\n");
 
-fn copy_reader_to_stdin<const N: usize>(
-    mut stdin: ChildStdin,
-    reader: &mut dyn Read,
-) -> Result<(), io::Error> {
-    const { assert!(N > 0, "reader buffer size must be greater than zero") };
+let mut command2 = Command::new("tr");
+command2.arg("a-z").arg("A-Z");
 
-    let mut buffer = [0u8; N];
+command2.stdout(Stdio::piped());
 
-    loop {
-        match reader.read(&mut buffer) {
-            Ok(0) => break,
-            Ok(c) => stdin.write_all(&buffer[0..c])?,
-            Err(ref err) if err.kind() == ErrorKind::Interrupted => (),
-            Err(err) => return Err(err),
-        }
-    }
+let output = command1
+    .execute_multiple_stages_output(vec![
+        Stage::function(|input, output| {
+            for line in BufReader::new(input).lines() {
+                let line = line?;
 
-    Ok(())
-}
+                if line.contains("an") {
+                    writeln!(output, "{}", line)?;
+                }
+            }
 
-fn wait_with_stdin_writer<T, W, F>(wait: W, write_stdin: F) -> Result<T, io::Error>
-where
-    T: Send,
-    W: FnOnce() -> Result<T, io::Error> + Send,
-    F: FnOnce() -> Result<(), io::Error>, {
-    thread::scope(|scope| {
-        // Waiting in a scoped thread lets the child drain its stdout and stderr while this thread is still writing stdin.
-        let wait_handle = scope.spawn(wait);
-        let write_result = write_stdin();
-        let wait_result = match wait_handle.join() {
-            Ok(result) => result,
-            Err(_) => Err(io::Error::other("child wait thread panicked")),
-        };
+            Ok(())
+        }),
+        Stage::from(&mut command2),
+    ])
+    .unwrap();
 
-        match write_result {
-            Ok(()) => wait_result,
-            Err(err) => Err(err),
-        }
-    })
-}
+assert_eq!(b"BANANA\n", output.stdout.as_slice());
+# }
+```
 
-fn kill_and_wait_children(mut children: Vec<Child>) {
-    // If pipeline setup fails, terminate every child we already spawned and reap them before returning the setup error.
-    for child in &mut children {
-        let _ = child.kill();
-    }
+#### Fan Out to Several Commands
 
-    for mut child in children {
-        let _ = child.wait();
-    }
-}
+The `execute_tee_output` and `execute_multiple_tee_output` methods copy the stdout of a command or a pipeline into several branch commands at the same time, like `tee >(sha256sum) >(gzip > out.gz)`, so the producer only runs once.
 
-fn wait_upstream_children(children: Vec<Child>) -> Result<(), io::Error> {
-    let mut first_error = None;
+```rust,ignore
+use std::process::{Command, Stdio};
 
-    for mut child in children {
-        if let Err(err) = child.wait() {
-            if first_error.is_none() {
-                first_error = Some(err);
-            }
-        }
-    }
+use execute::{Execute, Redirect, WriteMode};
 
-    match first_error {
-        Some(err) => Err(err),
-        None => Ok(()),
-    }
-}
+let mut pg_dump_command = Command::new("pg_dump");
 
-fn finish_pipeline_result<T>(
-    result: Result<T, io::Error>,
-    upstream_children: Vec<Child>,
-) -> Result<T, io::Error> {
-    // The public result follows normal shell pipeline behavior and comes from the last command, but upstream children still must be reaped.
-    let upstream_result = wait_upstream_children(upstream_children);
+pg_dump_command.arg("database");
 
-    match result {
-        Ok(value) => {
-            upstream_result?;
+let mut sha256sum_command = Command::new("sha256sum");
 
-            Ok(value)
-        },
-        Err(err) => Err(err),
-    }
-}
+sha256sum_command.stdout(Stdio::piped());
 
-fn spawn_pipeline(
-    first: &mut Command,
-    others: &mut [&mut Command],
-) -> Result<(Vec<Child>, Child), io::Error> {
-    let mut upstream_children = Vec::with_capacity(others.len());
-    let mut previous_child = first.spawn()?;
-    let last_index = others.len() - 1;
+let mut gzip_command = Command::new("gzip");
 
-    for other in others.iter_mut().take(last_index) {
-        // Each child becomes upstream as soon as its stdout is moved into the next command's stdin.
-        let stdout = match take_child_stdout(&mut previous_child) {
-            Ok(stdout) => stdout,
-            Err(err) => {
-                upstream_children.push(previous_child);
-                kill_and_wait_children(upstream_children);
+gzip_command.stdout_file("/path/to/dump.sql.gz", WriteMode::Truncate).unwrap();
 
-                return Err(err);
-            },
-        };
+let output = pg_dump_command
+    .execute_tee_output(&mut [&mut sha256sum_command, &mut gzip_command])
+    .unwrap();
 
-        other.stdin(stdout);
-        other.stdout(Stdio::piped());
-        other.stderr(Stdio::null());
+if output.status.success() && output.branches.iter().all(|branch| branch.status.success()) {
+    println!("{}", String::from_utf8(output.branches[0].stdout.clone()).unwrap());
+}
+```
+
+#### Spawn a Pipeline in Its Own Process Group
+
+On Unix, the `spawn_multiple_in_process_group` method spawns every process of a pipeline in a new process group without waiting for them. The returned `Pipeline` can kill the whole group, including the processes forked by its stages, such as the programs started by a `sh -c` stage.
+
+The `execute_multiple*` methods do not use a process group, so that their processes stay in the foreground process group of the terminal and are interrupted by Ctrl-C together with the parent, like a pipeline run by a shell. The flip side is that when they fail or are dropped early, only the processes of the stages are terminated, and the processes forked by the stages keep running. The group is only signaled while at least one process of the pipeline has not been reaped, because the system can reuse the group ID after that.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::Execute;
+
+# #[cfg(target_os = "linux")] {
+let mut command1 = execute::shell("sleep 60 & sleep 60");
+
+let mut command2 = Command::new("cat");
+
+command2.stdout(Stdio::piped());
+
+let mut pipeline = command1.spawn_multiple_in_process_group(&mut [&mut command2]).unwrap();
+
+pipeline.kill().unwrap();
+
+assert_eq!(None, pipeline.wait().unwrap());
+# }
+```
+
+#### Kill Processes When They Are Dropped
+
+A `Pipeline` which is dropped without being waited for, including during panic unwinding, kills all of its processes with `SIGKILL`, as well as its process group if it has one, and reaps them. A single spawned `Child` can be wrapped in a `ChildGuard` for the same behavior. Dropping never waits for a grace period, even during unwinding. Call `into_inner` to release the process from the guard.
+
+```rust
+use std::process::Command;
+
+use execute::ChildGuard;
+
+# if cfg!(target_os = "linux") {
+let guard = ChildGuard::new(Command::new("sleep").arg("60").spawn().unwrap());
+
+println!("{}", guard.id());
+
+drop(guard); // the sleep process is killed and reaped here
+# }
+```
+
+#### Terminate Processes Gracefully
+
+The `terminate` method of a `ChildGuard` or a `Pipeline` ends the processes gracefully before they are dropped. By default, they are terminated by sending `SIGTERM`, waiting for up to 5 seconds, and then sending `SIGKILL` to the ones still running, so programs which flush their output on `SIGTERM` can exit cleanly. The signal and the grace period can be changed with a `TerminationPolicy`. It reports which step actually ended the processes, and `wait_timeout` can be used to cancel processes which take too long. The `execute_multiple*` methods also end the processes of a pipeline this way when one of its stages cannot be spawned.
+
+```rust
+use std::{process::Command, time::Duration};
+
+use execute::{ChildGuard, Termination, TerminationPolicy};
+
+# if cfg!(target_os = "linux") {
+let mut guard = ChildGuard::new(Command::new("sleep").arg("60").spawn().unwrap());
+
+guard.set_termination_policy(TerminationPolicy::new().grace_period(Duration::from_secs(1)));
+
+if guard.wait_timeout(Duration::from_millis(100)).unwrap().is_none() {
+    assert_eq!(Termination::Signal, guard.terminate().unwrap());
+}
+# }
+```
+
+### Run Commands in Parallel with Dependencies
+
+A `TaskGraph` runs tasks in parallel like `make -j`, starting each one after the tasks it depends on have succeeded. A task can use any of the execution methods. By default, no more tasks are started after one fails, and `FailurePolicy::KeepGoing` only skips the tasks which depend on a failed one.
+
+```rust
+use std::process::Command;
+
+use execute::{Execute, TaskGraph};
+
+# if cfg!(target_os = "linux") {
+let mut generate_command = Command::new("echo");
+generate_command.arg("generate");
+
+let mut compile_command1 = Command::new("echo");
+compile_command1.arg("compile 1");
+
+let mut compile_command2 = Command::new("echo");
+compile_command2.arg("compile 2");
+
+let mut graph = TaskGraph::new();
+
+graph.set_concurrency(4);
+
+let generate = graph.add_command("generate", &[], &mut generate_command);
+let compile1 = graph.add_command("compile 1", &[generate], &mut compile_command1);
+let compile2 = graph.add_command("compile 2", &[generate], &mut compile_command2);
+
+graph.add("link", &[compile1, compile2], || execute::command("echo link").execute_output());
+
+for report in graph.run() {
+    println!("{}: {} in {:?}", report.name, report.success(), report.elapsed);
+}
+# }
+```
+
+### Run Many Commands in Parallel
+
+A `JobPool` runs independent commands with a limited number of them at once, and yields the result of every job in completion order or in input order. Each job can capture its output and take in-memory input. With `set_fail_fast(true)`, the pool stops taking jobs after one fails and terminates the jobs which are still running.
+
+```rust,ignore
+use std::fs;
+use std::process::{Command, Stdio};
+
+use execute::{JobOrder, JobPool};
+
+const FFMPEG_PATH: &str = "/path/to/ffmpeg";
+
+let mut pool = JobPool::new();
+
+pool.set_concurrency(4);
+pool.set_order(JobOrder::Input);
+pool.set_fail_fast(true);
+
+let commands = fs::read_dir("/path/to/media-files").unwrap().map(|entry| {
+    let path = entry.unwrap().path();
+
+    let mut command = Command::new(FFMPEG_PATH);
+
+    command.arg("-i");
+    command.arg(&path);
+    command.arg(path.with_extension("webm"));
+
+    command.stderr(Stdio::piped());
+
+    command
+});
+
+for report in pool.run(commands) {
+    if !report.success() {
+        eprintln!("Job {} failed: {:?}", report.index, report.result);
+    }
+}
+```
+
+### Pass Many Arguments in Batches
+
+The `execute_batches` method runs a command repeatedly with a large number of arguments split into batches, like `xargs`, so that no command line goes over the limit of the system and fails with `E2BIG`. Batches can also run in parallel.
+
+```rust
+use std::process::Command;
+
+use execute::{BatchOptions, Execute};
+
+# if cfg!(target_os = "linux") {
+let mut command = Command::new("touch");
+
+command.current_dir(std::env::temp_dir());
+
+let files = (0..10000).map(|i| format!("execute-batch-example-{}", i));
+
+let reports = command.execute_batches(files, BatchOptions::new().concurrency(4)).unwrap();
+
+assert!(reports.iter().all(|report| report.success()));
+#
+# let mut command = Command::new("rm");
+#
+# command.current_dir(std::env::temp_dir());
+#
+# let files = (0..10000).map(|i| format!("execute-batch-example-{}", i));
+#
+# command.execute_batches(files, BatchOptions::new()).unwrap();
+# }
+```
+
+The method only copies the program, arguments, environment variables and working directory of the command, because the other settings of a `Command` cannot be read. To keep them, such as its stdout or resource limits, pass a closure which creates the command to the `execute_batches` function instead.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::BatchOptions;
+
+# if cfg!(target_os = "linux") {
+let numbers = (0..10000).map(|i| i.to_string());
+
+let reports = execute::execute_batches(
+    || {
+        let mut command = Command::new("echo");
+
+        command.stdout(Stdio::null());
+
+        command
+    },
+    numbers,
+    BatchOptions::new(),
+)
+.unwrap();
+
+assert!(reports.iter().all(|report| report.success()));
+# }
+```
+
+### Run a Command String in the Current Shell
+
+The `shell` function can be used to create a `Command` instance with a single command string instead of a program name and scattered arguments.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::{Execute, shell};
+
+# if cfg!(target_os = "linux") {
+let mut command = shell("cat /proc/meminfo");
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_output().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+
+### Parse a Command String at Runtime
+
+The `command` function can be used to create a `Command` instance with a single command string instead of a program name and scattered arguments. The difference between the `shell` function and the `command` function is that the former is interpreted by the current shell while the latter is parsed by this crate.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::{Execute, command};
+
+# if cfg!(target_os = "linux") {
+let mut command = command("cat '/proc/meminfo'");
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_output().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+
+Leading `NAME=value` tokens are not treated as the program name. They are set as environment variables of the command instead, like `command("LC_ALL=C sort --version")`.
+
+### Parse Command Templates with Comments
+
+The `command_with_options` function and the `commands` function accept `ParseOptions`. `ParseOptions::script()` allows `#` comments and backslash-newline line continuations, so command templates can be stored in multi-line configuration files. The `commands` function parses one command per logical line.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::{Execute, ParseOptions, commands};
+
+# if cfg!(target_os = "linux") {
+let script = r"
+## Print the first line of the CPU information.
+head -n 1 \
+    /proc/cpuinfo
+";
+
+for mut command in commands(script, ParseOptions::script()) {
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_output().unwrap();
+
+    println!("{}", String::from_utf8(output.stdout).unwrap());
+}
+# }
+```
+
+### Inline Input Data in a Command String
+
+The `command_with_input` function and the `commands_with_input` function also parse `<<<word` here-strings and `<<DELIMITER` here-documents, and return the stdin data along with the `Command` instance. If any part of the delimiter is quoted, the body is taken literally.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::{Execute, ParseOptions, command_with_input};
+
+# if cfg!(target_os = "linux") {
+let (mut command, input) = command_with_input(
+    "tr a-z A-Z <<'EOF'\nhello\nEOF",
+    ParseOptions::new(),
+);
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_input_output(&input.unwrap_or_default()).unwrap();
+
+assert_eq!(b"HELLO\n", output.stdout.as_slice());
+# }
+```
+
+### Expand Globs in a Command String
+
+The `command_with_glob` function expands the unquoted `*`, `?`, `[...]` and `**` patterns against a directory, which also becomes the working directory of the command. Quoted patterns stay literal. `NoMatchPolicy` decides whether a pattern matching nothing is passed through literally or becomes an error.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::{Execute, NoMatchPolicy, ParseOptions, command_with_glob};
+
+# if cfg!(target_os = "linux") {
+let mut command =
+    command_with_glob("ls -d sys*", ParseOptions::new(), "/proc", NoMatchPolicy::Error).unwrap();
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_output().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+
+### Parse a Command String at Compile Time
+
+The `command!` macro can be used to create a `Command` instance with a single command string literal instead of a program name and scattered arguments.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::Execute;
+
+# if cfg!(target_os = "linux") {
+let mut command = execute::command!("cat '/proc/meminfo'");
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_output().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+
+### Create a `Command` Instance by Providing Arguments Separately
+
+The `command_args!` macro can be used to create a `Command` instance with a program name and arguments separately. The program name and arguments can be non-literal.
+
+```rust
+use std::process::{Command, Stdio};
+
+use execute::Execute;
+
+# if cfg!(target_os = "linux") {
+let mut command = execute::command_args!("cat", "/proc/meminfo");
+
+command.stdout(Stdio::piped());
+
+let output = command.execute_output().unwrap();
+
+println!("{}", String::from_utf8(output.stdout).unwrap());
+# }
+```
+*/
+
+mod batch;
+mod graph;
+mod guard;
+mod pipe;
+mod pipeline;
+mod pool;
+#[cfg(unix)]
+mod process_group;
+#[cfg(target_os = "linux")]
+mod pty;
+mod redirect;
+mod resource_limits;
+mod resource_usage;
+mod session;
+mod stage;
+mod status;
+mod tee;
+mod termination;
+mod transcript;
+
+#[cfg(unix)]
+use std::{env, ffi::OsString};
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{self, BufRead, BufWriter, ErrorKind, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Output, Stdio},
+    thread,
+    time::{Instant, SystemTime},
+};
+
+pub use batch::{BatchOptions, BatchReport, execute_batches};
+pub use execute_command_macro::{command, command_args};
+pub use execute_command_tokens::{NoMatchPolicy, ParseOptions};
+use execute_command_tokens::{
+    ParsedCommand, Token, expand_globs, parse_command, parse_script, split_env_assignments,
+    tokenize, tokenize_script,
+};
+pub use graph::{FailurePolicy, TaskGraph, TaskId, TaskOutcome, TaskOutput, TaskReport};
+pub use guard::ChildGuard;
+use guard::KillHandle;
+pub use pipeline::Pipeline;
+pub use pool::{Job, JobOrder, JobPool, JobReport, JobResults};
+#[cfg(unix)]
+pub use process_group::ProcessGroup;
+#[cfg(target_os = "linux")]
+pub use pty::PtyOptions;
+pub use redirect::{Redirect, WriteMode};
+pub use resource_limits::LimitExceeded;
+#[cfg(unix)]
+pub use resource_limits::ResourceLimits;
+pub use resource_usage::ResourceUsage;
+pub use session::Session;
+pub use stage::{Stage, StageFunction};
+pub use status::{ProcessStatus, signal_name};
+pub use tee::TeeOutput;
+pub use termination::{Termination, TerminationPolicy};
+pub use transcript::{Stream, Transcript, TranscriptChunk};
+
+const DEFAULT_READER_BUFFER_SIZE: usize = 256;
+
+#[inline]
+fn take_child_stdin(child: &mut Child) -> Result<ChildStdin, io::Error> {
+    child.stdin.take().ok_or_else(|| io::Error::other("child stdin was not piped"))
+}
+
+#[inline]
+fn take_child_stdout(child: &mut Child) -> Result<ChildStdout, io::Error> {
+    child.stdout.take().ok_or_else(|| io::Error::other("child stdout was not piped"))
+}
+
+#[inline]
+fn write_stdin<D: ?Sized + AsRef<[u8]>>(stdin: &mut ChildStdin, data: &D) -> Result<(), io::Error> {
+    stdin.write_all(data.as_ref())
+}
+
+fn copy_reader_to_stdin<const N: usize>(
+    stdin: &mut ChildStdin,
+    reader: &mut dyn Read,
+) -> Result<(), io::Error> {
+    const { assert!(N > 0, "reader buffer size must be greater than zero") };
+
+    copy_reader_to_stdin_with_buffer(stdin, reader, &mut [0u8; N])
+}
+
+fn copy_reader_to_stdin_with_capacity(
+    stdin: &mut ChildStdin,
+    reader: &mut dyn Read,
+    capacity: usize,
+) -> Result<(), io::Error> {
+    copy_reader_to_stdin_with_buffer(stdin, reader, &mut vec![0u8; capacity])
+}
+
+fn copy_reader_to_stdin_with_buffer(
+    stdin: &mut ChildStdin,
+    reader: &mut dyn Read,
+    buffer: &mut [u8],
+) -> Result<(), io::Error> {
+    loop {
+        match reader.read(buffer) {
+            Ok(0) => break,
+            Ok(c) => stdin.write_all(&buffer[0..c])?,
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+fn copy_buf_reader_to_stdin(
+    stdin: &mut ChildStdin,
+    reader: &mut dyn BufRead,
+) -> Result<(), io::Error> {
+    loop {
+        let length = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(buffer) => {
+                stdin.write_all(buffer)?;
+
+                buffer.len()
+            },
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        reader.consume(length);
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn check_reader_capacity(capacity: usize) -> Result<(), io::Error> {
+    if capacity == 0 {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "reader buffer size must be greater than zero",
+        ));
+    }
+
+    Ok(())
+}
+
+fn write_stdin_with<F>(stdin: &mut ChildStdin, write: F) -> Result<(), io::Error>
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), io::Error>, {
+    // Closures usually write many small pieces, such as one record at a time.
+    let mut stdin = BufWriter::new(stdin);
+
+    write(&mut stdin)?;
+
+    stdin.flush()
+}
+
+fn read_merged_output(mut reader: File) -> Result<Vec<u8>, io::Error> {
+    let mut output = Vec::new();
+
+    reader.read_to_end(&mut output)?;
+
+    Ok(output)
+}
+
+/// Write stdin with `write_stdin` while waiting with `wait` in another thread. If `write_stdin` panics, the processes are killed before stdin is closed, so that they never take the partial input for a complete one.
+fn wait_with_stdin_writer<T, W, F>(
+    mut stdin: ChildStdin,
+    kill_handles: &[KillHandle],
+    wait: W,
+    write_stdin: F,
+) -> Result<T, io::Error>
+where
+    T: Send,
+    W: FnOnce() -> Result<T, io::Error> + Send,
+    F: FnOnce(&mut ChildStdin) -> Result<(), io::Error>, {
+    let (write_result, wait_result) = thread::scope(|scope| {
+        // Waiting in a scoped thread lets the child drain its stdout and stderr while this thread is still writing stdin.
+        let wait_handle = scope.spawn(wait);
+        let write_result = panic::catch_unwind(AssertUnwindSafe(|| write_stdin(&mut stdin)));
+
+        if write_result.is_err() {
+            for kill_handle in kill_handles {
+                let _ = kill_handle.kill();
+            }
+        }
+
+        drop(stdin);
+
+        let wait_result = match wait_handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(io::Error::other("child wait thread panicked")),
+        };
+
+        (write_result, wait_result)
+    });
+
+    match write_result {
+        Ok(Ok(())) => wait_result,
+        Ok(Err(err)) => Err(err),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+pub trait Execute {
+    /// Execute this command and get the exit status code. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent.
+    fn execute(&mut self) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command and get the exit status, including the signal which terminated the process. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent.
+    fn execute_status(&mut self) -> Result<ProcessStatus, io::Error>;
+
+    /// Execute this command and get the exit status along with the resources used by the process, which is reaped with `wait4`. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent.
+    #[cfg(target_os = "linux")]
+    fn execute_with_resource_usage(&mut self) -> Result<ProcessStatus, io::Error>;
+
+    /// Execute this command and get the exit status code. By default, stdin, stdout and stderr are inherited from the parent.
+    fn execute_output(&mut self) -> Result<Output, io::Error>;
+
+    /// Execute this command like [`execute_output`](Self::execute_output), and get the output along with the exit status, which has the resources used by the process. The process is reaped with `wait4`. By default, stdin, stdout and stderr are inherited from the parent.
+    #[cfg(target_os = "linux")]
+    fn execute_output_with_resource_usage(&mut self) -> Result<(Output, ProcessStatus), io::Error>;
+
+    /// Execute this command and capture its stdout and stderr together in the order they are written, like `2>&1`. The merged output is collected into `Output::stdout`. stdout and stderr will be set to the same pipe, and reset to being inherited from the parent after spawning. By default, stdin is inherited from the parent.
+    fn execute_output_merged(&mut self) -> Result<Output, io::Error>;
+
+    /// Execute this command and record its stdout and stderr as timestamped chunks in the order they arrive. stdout and stderr will be set to `Stdio::piped()`. By default, stdin is inherited from the parent.
+    fn execute_transcript(&mut self) -> Result<Transcript, io::Error>;
+
+    /// Execute this command and copy its stdout into `writer` while it runs, without buffering the whole output in memory. The returned `Output::stdout` is empty. See [`ChildGuard::wait_with_output_to_writer`]. stdout will be set to `Stdio::piped()`. By default, stdin and stderr are inherited from the parent.
+    fn execute_output_to_writer(&mut self, writer: &mut dyn Write) -> Result<Output, io::Error>;
+
+    /// Execute this command and check the exit status code. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent. It's usually used for checking whether the program is correct.
+    #[inline]
+    fn execute_check_exit_status_code(
+        &mut self,
+        expected_exit_status_code: i32,
+    ) -> Result<(), io::Error> {
+        match self.execute()? {
+            Some(exit_status_code) if exit_status_code == expected_exit_status_code => Ok(()),
+            _ => Err(io::Error::other("unexpected exit status")),
+        }
+    }
+
+    /// Execute this command and input in-memory data to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command attached to a new pseudo-terminal, so that it behaves as if it were run interactively, and capture the output. stdin, stdout and stderr will all be set to the terminal, and reset to being inherited from the parent after spawning. A `pre_exec` closure which makes the process the leader of a new session is added to this command, and disabled after spawning, because closures cannot be removed from a `Command`. The output of both stdout and stderr is collected into `Output::stdout`, with newlines translated into `\r\n` by the terminal. An end of input is sent to the process right away.
+    #[cfg(target_os = "linux")]
+    fn execute_pty_output(&mut self, options: PtyOptions) -> Result<Output, io::Error>;
+
+    /// Execute this command attached to a new pseudo-terminal, input in-memory data to the process followed by an end of input, and capture the output. See [`execute_pty_output`](Self::execute_pty_output). The terminal is in canonical mode, so each line of the data must not be longer than 4095 bytes.
+    #[cfg(target_os = "linux")]
+    fn execute_pty_input_output<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+        options: PtyOptions,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command and input in-memory data to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    fn execute_input_output<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    #[inline]
+    fn execute_input_reader(&mut self, reader: &mut dyn Read) -> Result<Option<i32>, io::Error> {
+        self.execute_input_reader2::<DEFAULT_READER_BUFFER_SIZE>(reader)
+    }
+
+    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input_reader2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    #[inline]
+    fn execute_input_reader_output(&mut self, reader: &mut dyn Read) -> Result<Output, io::Error> {
+        self.execute_input_reader_output2::<DEFAULT_READER_BUFFER_SIZE>(reader)
+    }
+
+    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    fn execute_input_reader_output2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command and input data from a reader to the process, through a buffer of `capacity` bytes allocated on the heap. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input_reader_with_capacity(
+        &mut self,
+        reader: &mut dyn Read,
+        capacity: usize,
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command and input data from a reader to the process, through a buffer of `capacity` bytes allocated on the heap. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    fn execute_input_reader_output_with_capacity(
+        &mut self,
+        reader: &mut dyn Read,
+        capacity: usize,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command and input data from a buffered reader to the process. The data is written straight from the buffer of the reader, without another copy. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input_buf_reader(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command and input data from a buffered reader to the process. The data is written straight from the buffer of the reader, without another copy. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    fn execute_input_buf_reader_output(
+        &mut self,
+        reader: &mut dyn BufRead,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command with the file at `path` as its stdin. The file is opened and handed to the process, so the data is read by the process directly instead of being copied through this process. stdin will be reset to being inherited from the parent after spawning. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command with the file at `path` as its stdin. See [`execute_input_file`](Self::execute_input_file). By default, stdout and stderr are inherited from the parent.
+    fn execute_input_file_output<P: AsRef<Path>>(&mut self, path: P) -> Result<Output, io::Error>;
+
+    /// Execute this command and input data produced incrementally by a closure, which writes to the stdin of the process. The writes are buffered, and stdin is closed after the closure returns. If the closure panics, the process is killed on Unix before stdin is closed, so it never takes the partial input for a complete one. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
+    fn execute_input_writer<F>(&mut self, write: F) -> Result<Option<i32>, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>;
+
+    /// Execute this command and input data produced incrementally by a closure, which writes to the stdin of the process. The writes are buffered, and stdin is closed after the closure returns. If the closure panics, the process is killed on Unix before stdin is closed, so it never takes the partial input for a complete one. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
+    fn execute_input_writer_output<F>(&mut self, write: F) -> Result<Output, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>;
+
+    /// Spawn this command as well as other commands and pipe their stdin and stdout without waiting for them. The stderr of every process except the last one will be set to `Stdio::null()`. The stdin of every other process will be reset to being inherited from the parent after spawning. By default, the stdin of the first process, the stdout and stderr of the last process are inherited from the parent. `others` can be empty.
+    fn spawn_multiple(&mut self, others: &mut [&mut Command]) -> Result<Pipeline, io::Error>;
+
+    /// Spawn this command as well as other commands like `spawn_multiple`, but put every process into a new process group led by the first process, so that the whole pipeline, including the processes forked by its stages, can be signaled or killed through the returned `Pipeline`. Processes in the new group do not receive the signals generated by the terminal, such as `SIGINT` from Ctrl-C.
+    #[cfg(unix)]
+    fn spawn_multiple_in_process_group(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Pipeline, io::Error>;
+
+    // TODO execute_multiple
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and get the exit status code. The stdout and stderr of the last process will be set to `Stdio::null()`. By default, the stdin of the first process is inherited from the parent.
+    fn execute_multiple(&mut self, others: &mut [&mut Command]) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and get the exit status of the last process, including the signal which terminated it. The stdout and stderr of the last process will be set to `Stdio::null()`. By default, the stdin of the first process is inherited from the parent.
+    fn execute_multiple_status(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<ProcessStatus, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and get the exit status of every process in pipeline order along with the resources it used. The processes are reaped with `wait4`. The stdout and stderr of the last process will be set to `Stdio::null()`. By default, the stdin of the first process is inherited from the parent.
+    #[cfg(target_os = "linux")]
+    fn execute_multiple_with_resource_usage(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Vec<ProcessStatus>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout. By default, the stdin of the first process, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_output(&mut self, others: &mut [&mut Command])
+    -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands like [`execute_multiple_output`](Self::execute_multiple_output), and get the output of the last process along with the exit status of every process in pipeline order, which has the resources it used. The processes are reaped with `wait4`. By default, the stdin of the first process, the stdout and stderr of the last process are inherited from the parent.
+    #[cfg(target_os = "linux")]
+    fn execute_multiple_output_with_resource_usage(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<(Output, Vec<ProcessStatus>), io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and capture the stdout and stderr of the last process together in the order they are written, like `2>&1`. The merged output is collected into `Output::stdout`. See [`execute_output_merged`](Self::execute_output_merged). By default, the stdin of the first process is inherited from the parent.
+    fn execute_multiple_output_merged(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and record the stdout and stderr of the last process as timestamped chunks in the order they arrive. The stdout and stderr of the last process will be set to `Stdio::piped()`. By default, the stdin of the first process is inherited from the parent.
+    fn execute_multiple_transcript(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Transcript, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and copy the stdout of the last process into `writer` while they run. See [`execute_output_to_writer`](Self::execute_output_to_writer). The stdout of the last process will be set to `Stdio::piped()`. By default, the stdin of the first process and the stderr of the last process are inherited from the parent.
+    fn execute_multiple_output_to_writer(
+        &mut self,
+        writer: &mut dyn Write,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input in-memory data to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input in-memory data to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_output<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    #[inline]
+    fn execute_multiple_input_reader(
+        &mut self,
+        reader: &mut dyn Read,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error> {
+        self.execute_multiple_input_reader2::<DEFAULT_READER_BUFFER_SIZE>(reader, others)
+    }
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input_reader2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    #[inline]
+    fn execute_multiple_input_reader_output(
+        &mut self,
+        reader: &mut dyn Read,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error> {
+        self.execute_multiple_input_reader_output2::<DEFAULT_READER_BUFFER_SIZE>(reader, others)
+    }
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_reader_output2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process through a buffer of `capacity` bytes allocated on the heap, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input_reader_with_capacity(
+        &mut self,
+        reader: &mut dyn Read,
+        capacity: usize,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process through a buffer of `capacity` bytes allocated on the heap. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_reader_output_with_capacity(
+        &mut self,
+        reader: &mut dyn Read,
+        capacity: usize,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a buffered reader to the process without another copy, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input_buf_reader(
+        &mut self,
+        reader: &mut dyn BufRead,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a buffered reader to the process without another copy. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_buf_reader_output(
+        &mut self,
+        reader: &mut dyn BufRead,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, with the file at `path` as the stdin of the first process, and get the exit status code. See [`execute_input_file`](Self::execute_input_file). The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, with the file at `path` as the stdin of the first process. See [`execute_input_file`](Self::execute_input_file). By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_file_output<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data produced incrementally by a closure, which writes to the stdin of the first process, and get the exit status code. See [`execute_input_writer`](Self::execute_input_writer). The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
+    fn execute_multiple_input_writer<F>(
+        &mut self,
+        write: F,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data produced incrementally by a closure, which writes to the stdin of the first process. See [`execute_input_writer`](Self::execute_input_writer). The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_input_writer_output<F>(
+        &mut self,
+        write: F,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>;
+
+    /// Execute this command as well as other stages and pipe their stdin and stdout, and get the exit status code of the last stage. A stage can be an external command or a Rust function run on its own thread, which reads the output of the previous stage and writes the input of the next one. An error returned by a function is returned after every process has been reaped. The last stage must be a command. The stdout and stderr of the last process will be set to `Stdio::null()`. By default, the stdin of the first process is inherited from the parent.
+    fn execute_multiple_stages(&mut self, stages: Vec<Stage<'_>>)
+    -> Result<Option<i32>, io::Error>;
+
+    /// Execute this command as well as other stages and pipe their stdin and stdout. See [`execute_multiple_stages`](Self::execute_multiple_stages). By default, the stdin of the first process, the stdout and stderr of the last process are inherited from the parent.
+    fn execute_multiple_stages_output(
+        &mut self,
+        stages: Vec<Stage<'_>>,
+    ) -> Result<Output, io::Error>;
+
+    /// Execute this command and copy its stdout into the stdin of every branch command at the same time, like `tee >(command1) >(command2)`, and get the exit status of this command and the output of every branch. The slowest branch limits the speed, so the memory used stays bounded. A branch which exits without reading all of its input does not affect the others. stdout will be set to `Stdio::piped()`. The stdin of every branch will be set to `Stdio::piped()`. By default, the stdin and stderr of this command, and the stdout and stderr of every branch are inherited from the parent.
+    fn execute_tee_output(&mut self, branches: &mut [&mut Command])
+    -> Result<TeeOutput, io::Error>;
+
+    /// Execute this command as well as other commands and pipe their stdin and stdout, and copy the stdout of the last process into the stdin of every branch command at the same time. See [`execute_tee_output`](Self::execute_tee_output). The stdout of the last process will be set to `Stdio::piped()`. By default, the stdin of the first process and the stderr of the last process are inherited from the parent.
+    fn execute_multiple_tee_output(
+        &mut self,
+        others: &mut [&mut Command],
+        branches: &mut [&mut Command],
+    ) -> Result<TeeOutput, io::Error>;
+
+    /// Execute this command repeatedly with `args` appended in batches, like `xargs`, so that every command line stays under the limit of the system, which is `ARG_MAX` on Unix, taking the environment into account, and the maximum length of a command line on Windows. Only the program, arguments, environment variables and working directory of this command are used, since the other settings of a `Command` cannot be read. Its stdout and stderr, process group and `pre_exec` closures, such as the ones of [`ResourceLimits`], are not applied to the batches. To keep them, use the [`execute_batches`] function with a closure which creates the command. The stdin of every batch will be set to `Stdio::null()`, and its stdout and stderr are inherited from the parent. Returns the result of every batch in order, or an error of kind `InvalidInput` without running anything if an argument cannot fit in a command line.
+    fn execute_batches<I, S>(
+        &mut self,
+        args: I,
+        options: BatchOptions,
+    ) -> Result<Vec<BatchReport>, io::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>;
+}
+
+impl Execute for Command {
+    #[inline]
+    fn execute(&mut self) -> Result<Option<i32>, io::Error> {
+        self.execute_status().map(|status| status.code())
+    }
+
+    #[inline]
+    fn execute_status(&mut self) -> Result<ProcessStatus, io::Error> {
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        Ok(self.status()?.into())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn execute_with_resource_usage(&mut self) -> Result<ProcessStatus, io::Error> {
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        ChildGuard::new(self.spawn()?).wait_with_resource_usage()
+    }
+
+    #[inline]
+    fn execute_output(&mut self) -> Result<Output, io::Error> {
+        ChildGuard::new(self.spawn()?).wait_with_output()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn execute_output_with_resource_usage(&mut self) -> Result<(Output, ProcessStatus), io::Error> {
+        ChildGuard::new(self.spawn()?).wait_with_output_and_resource_usage()
+    }
+
+    fn execute_transcript(&mut self) -> Result<Transcript, io::Error> {
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::piped());
+
+        let started = Instant::now();
+        let started_at = SystemTime::now();
+
+        transcript::record(&mut ChildGuard::new(self.spawn()?), started, started_at)
+    }
+
+    #[inline]
+    fn execute_output_to_writer(&mut self, writer: &mut dyn Write) -> Result<Output, io::Error> {
+        self.stdout(Stdio::piped());
+
+        ChildGuard::new(self.spawn()?).wait_with_output_to_writer(writer)
+    }
+
+    fn execute_output_merged(&mut self) -> Result<Output, io::Error> {
+        let reader = redirect::merge_output(self)?;
+        let child = self.spawn();
+
+        redirect::reset_merged_output(self);
+
+        let mut child = ChildGuard::new(child?);
+
+        drop(child.stdin.take());
+
+        let stdout = read_merged_output(reader)?;
+
+        Ok(Output {
+            status: child.wait()?,
+            stdout,
+            stderr: Vec::new(),
+        })
+    }
+
+    #[inline]
+    fn execute_input<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+    ) -> Result<Option<i32>, io::Error> {
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait().map(|status| status.code()),
+            |stdin| write_stdin(stdin, data),
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn execute_pty_output(&mut self, options: PtyOptions) -> Result<Output, io::Error> {
+        pty::execute_pty(self, options, &[])
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    fn execute_pty_input_output<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+        options: PtyOptions,
+    ) -> Result<Output, io::Error> {
+        pty::execute_pty(self, options, data.as_ref())
+    }
 
-        upstream_children.push(previous_child);
+    #[inline]
+    fn execute_input_output<D: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        data: &D,
+    ) -> Result<Output, io::Error> {
+        self.stdin(Stdio::piped());
 
-        previous_child = match other.spawn() {
-            Ok(child) => child,
-            Err(err) => {
-                kill_and_wait_children(upstream_children);
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
 
-                return Err(err);
-            },
-        };
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait_with_output(),
+            |stdin| write_stdin(stdin, data),
+        )
     }
 
-    let stdout = match take_child_stdout(&mut previous_child) {
-        Ok(stdout) => stdout,
-        Err(err) => {
-            upstream_children.push(previous_child);
-            kill_and_wait_children(upstream_children);
+    #[inline]
+    fn execute_input_reader2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+    ) -> Result<Option<i32>, io::Error> {
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
 
-            return Err(err);
-        },
-    };
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
 
-    let last_other = &mut others[last_index];
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait().map(|status| status.code()),
+            |stdin| copy_reader_to_stdin::<N>(stdin, reader),
+        )
+    }
 
-    last_other.stdin(stdout);
-    upstream_children.push(previous_child);
+    #[inline]
+    fn execute_input_reader_output2<const N: usize>(
+        &mut self,
+        reader: &mut dyn Read,
+    ) -> Result<Output, io::Error> {
+        self.stdin(Stdio::piped());
 
-    // Keep the last child separate because callers need its status or captured output as the method result.
-    match last_other.spawn() {
-        Ok(last_child) => Ok((upstream_children, last_child)),
-        Err(err) => {
-            kill_and_wait_children(upstream_children);
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
 
-            Err(err)
-        },
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait_with_output(),
+            |stdin| copy_reader_to_stdin::<N>(stdin, reader),
+        )
     }
-}
 
-pub trait Execute {
-    /// Execute this command and get the exit status code. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent.
-    fn execute(&mut self) -> Result<Option<i32>, io::Error>;
+    #[inline]
+    fn execute_input_reader_with_capacity(
+        &mut self,
+        reader: &mut dyn Read,
+        capacity: usize,
+    ) -> Result<Option<i32>, io::Error> {
+        check_reader_capacity(capacity)?;
 
-    /// Execute this command and get the exit status code. By default, stdin, stdout and stderr are inherited from the parent.
-    fn execute_output(&mut self) -> Result<Output, io::Error>;
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait().map(|status| status.code()),
+            |stdin| copy_reader_to_stdin_with_capacity(stdin, reader, capacity),
+        )
+    }
 
-    /// Execute this command and check the exit status code. stdout and stderr will be set to `Stdio::null()`. By default, stdin is inherited from the parent. It's usually used for checking whether the program is correct.
     #[inline]
-    fn execute_check_exit_status_code(
+    fn execute_input_reader_output_with_capacity(
         &mut self,
-        expected_exit_status_code: i32,
-    ) -> Result<(), io::Error> {
-        match self.execute()? {
-            Some(exit_status_code) if exit_status_code == expected_exit_status_code => Ok(()),
-            _ => Err(io::Error::other("unexpected exit status")),
-        }
+        reader: &mut dyn Read,
+        capacity: usize,
+    ) -> Result<Output, io::Error> {
+        check_reader_capacity(capacity)?;
+
+        self.stdin(Stdio::piped());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait_with_output(),
+            |stdin| copy_reader_to_stdin_with_capacity(stdin, reader, capacity),
+        )
     }
 
-    /// Execute this command and input in-memory data to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
-    fn execute_input<D: ?Sized + AsRef<[u8]>>(
+    #[inline]
+    fn execute_input_buf_reader(
         &mut self,
-        data: &D,
-    ) -> Result<Option<i32>, io::Error>;
+        reader: &mut dyn BufRead,
+    ) -> Result<Option<i32>, io::Error> {
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
 
-    /// Execute this command and input in-memory data to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
-    fn execute_input_output<D: ?Sized + AsRef<[u8]>>(
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait().map(|status| status.code()),
+            |stdin| copy_buf_reader_to_stdin(stdin, reader),
+        )
+    }
+
+    #[inline]
+    fn execute_input_buf_reader_output(
         &mut self,
-        data: &D,
-    ) -> Result<Output, io::Error>;
+        reader: &mut dyn BufRead,
+    ) -> Result<Output, io::Error> {
+        self.stdin(Stdio::piped());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait_with_output(),
+            |stdin| copy_buf_reader_to_stdin(stdin, reader),
+        )
+    }
+
+    fn execute_input_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Option<i32>, io::Error> {
+        self.stdin(File::open(path)?);
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        let child = self.spawn();
+
+        // The `Command` would keep the file open otherwise.
+        self.stdin(Stdio::inherit());
+
+        Ok(ChildGuard::new(child?).wait()?.code())
+    }
+
+    fn execute_input_file_output<P: AsRef<Path>>(&mut self, path: P) -> Result<Output, io::Error> {
+        self.stdin(File::open(path)?);
+
+        let child = self.spawn();
+
+        self.stdin(Stdio::inherit());
+
+        ChildGuard::new(child?).wait_with_output()
+    }
 
-    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
     #[inline]
-    fn execute_input_reader(&mut self, reader: &mut dyn Read) -> Result<Option<i32>, io::Error> {
-        self.execute_input_reader2::<DEFAULT_READER_BUFFER_SIZE>(reader)
+    fn execute_input_writer<F>(&mut self, write: F) -> Result<Option<i32>, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>, {
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::null());
+        self.stderr(Stdio::null());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait().map(|status| status.code()),
+            |stdin| write_stdin_with(stdin, write),
+        )
     }
 
-    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. stdout and stderr will be set to `Stdio::null()`.
-    fn execute_input_reader2<const N: usize>(
+    #[inline]
+    fn execute_input_writer_output<F>(&mut self, write: F) -> Result<Output, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>, {
+        self.stdin(Stdio::piped());
+
+        let mut child = ChildGuard::new(self.spawn()?);
+        let stdin = take_child_stdin(&mut child)?;
+        let kill_handles = [child.kill_handle()?];
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || child.wait_with_output(),
+            |stdin| write_stdin_with(stdin, write),
+        )
+    }
+
+    #[inline]
+    fn spawn_multiple(&mut self, others: &mut [&mut Command]) -> Result<Pipeline, io::Error> {
+        if !others.is_empty() {
+            self.stdout(Stdio::piped());
+            self.stderr(Stdio::null());
+        }
+
+        Pipeline::spawn(self, others, false)
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn spawn_multiple_in_process_group(
         &mut self,
-        reader: &mut dyn Read,
-    ) -> Result<Option<i32>, io::Error>;
+        others: &mut [&mut Command],
+    ) -> Result<Pipeline, io::Error> {
+        if !others.is_empty() {
+            self.stdout(Stdio::piped());
+            self.stderr(Stdio::null());
+        }
+
+        Pipeline::spawn(self, others, true)
+    }
 
-    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
     #[inline]
-    fn execute_input_reader_output(&mut self, reader: &mut dyn Read) -> Result<Output, io::Error> {
-        self.execute_input_reader_output2::<DEFAULT_READER_BUFFER_SIZE>(reader)
+    fn execute_multiple(&mut self, others: &mut [&mut Command]) -> Result<Option<i32>, io::Error> {
+        self.execute_multiple_status(others).map(|status| status.code())
+    }
+
+    fn execute_multiple_status(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<ProcessStatus, io::Error> {
+        if others.is_empty() {
+            return self.execute_status();
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        Pipeline::spawn(self, others, false)?.wait_status()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn execute_multiple_with_resource_usage(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Vec<ProcessStatus>, io::Error> {
+        if others.is_empty() {
+            return self.execute_with_resource_usage().map(|status| vec![status]);
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        Pipeline::spawn(self, others, false)?.wait_with_resource_usage()
+    }
+
+    fn execute_multiple_output(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_output();
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        Pipeline::spawn(self, others, false)?.wait_with_output()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn execute_multiple_output_with_resource_usage(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<(Output, Vec<ProcessStatus>), io::Error> {
+        if others.is_empty() {
+            return self
+                .execute_output_with_resource_usage()
+                .map(|(output, status)| (output, vec![status]));
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        Pipeline::spawn(self, others, false)?.wait_with_output_and_resource_usage()
+    }
+
+    fn execute_multiple_transcript(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Transcript, io::Error> {
+        if others.is_empty() {
+            return self.execute_transcript();
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::piped());
+        last_other.stderr(Stdio::piped());
+
+        let started = Instant::now();
+        let started_at = SystemTime::now();
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+
+        let transcript = transcript::record(pipeline.last_child_mut(), started, started_at)?;
+
+        // The last child has been reaped, so this only reaps the upstream children.
+        pipeline.wait()?;
+
+        Ok(transcript)
+    }
+
+    fn execute_multiple_output_to_writer(
+        &mut self,
+        writer: &mut dyn Write,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_output_to_writer(writer);
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+
+        others[others_length_dec].stdout(Stdio::piped());
+
+        Pipeline::spawn(self, others, false)?.wait_with_output_to_writer(writer)
     }
 
-    /// Execute this command and input data from a reader to the process. stdin will be set to `Stdio::piped()`. By default, stdout and stderr are inherited from the parent.
-    fn execute_input_reader_output2<const N: usize>(
-        &mut self,
-        reader: &mut dyn Read,
-    ) -> Result<Output, io::Error>;
+    fn execute_multiple_output_merged(
+        &mut self,
+        others: &mut [&mut Command],
+    ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_output_merged();
+        }
+
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
 
-    // TODO execute_multiple
+        let reader = redirect::merge_output(others[others_length_dec])?;
+        let pipeline = Pipeline::spawn(self, others, false);
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and get the exit status code. The stdout and stderr of the last process will be set to `Stdio::null()`. By default, the stdin of the first process is inherited from the parent.
-    fn execute_multiple(&mut self, others: &mut [&mut Command]) -> Result<Option<i32>, io::Error>;
+        redirect::reset_merged_output(others[others_length_dec]);
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout. By default, the stdin of the first process, the stdout and stderr of the last process are inherited from the parent.
-    fn execute_multiple_output(&mut self, others: &mut [&mut Command])
-    -> Result<Output, io::Error>;
+        let pipeline = pipeline?;
+
+        let stdout = read_merged_output(reader)?;
+
+        Ok(Output {
+            status: pipeline.wait_status()?.exit_status(),
+            stdout,
+            stderr: Vec::new(),
+        })
+    }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input in-memory data to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
     fn execute_multiple_input<D: ?Sized + AsRef<[u8]>>(
         &mut self,
         data: &D,
         others: &mut [&mut Command],
-    ) -> Result<Option<i32>, io::Error>;
+    ) -> Result<Option<i32>, io::Error> {
+        if others.is_empty() {
+            return self.execute_input(data);
+        }
+
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait(),
+            |stdin| write_stdin(stdin, data),
+        )
+    }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input in-memory data to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
     fn execute_multiple_input_output<D: ?Sized + AsRef<[u8]>>(
         &mut self,
         data: &D,
         others: &mut [&mut Command],
-    ) -> Result<Output, io::Error>;
+    ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_output(data);
+        }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
-    #[inline]
-    fn execute_multiple_input_reader(
-        &mut self,
-        reader: &mut dyn Read,
-        others: &mut [&mut Command],
-    ) -> Result<Option<i32>, io::Error> {
-        self.execute_multiple_input_reader2::<DEFAULT_READER_BUFFER_SIZE>(reader, others)
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait_with_output(),
+            |stdin| write_stdin(stdin, data),
+        )
     }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process, and get the exit status code. The stdin of the first process will be set to `Stdio::piped()`. The stdout and stderr of the last process will be set to `Stdio::null()`.
     fn execute_multiple_input_reader2<const N: usize>(
         &mut self,
         reader: &mut dyn Read,
         others: &mut [&mut Command],
-    ) -> Result<Option<i32>, io::Error>;
+    ) -> Result<Option<i32>, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_reader2::<N>(reader);
+        }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
-    #[inline]
-    fn execute_multiple_input_reader_output(
-        &mut self,
-        reader: &mut dyn Read,
-        others: &mut [&mut Command],
-    ) -> Result<Output, io::Error> {
-        self.execute_multiple_input_reader_output2::<DEFAULT_READER_BUFFER_SIZE>(reader, others)
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
+
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
+
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait(),
+            |stdin| copy_reader_to_stdin::<N>(stdin, reader),
+        )
     }
 
-    /// Execute this command as well as other commands and pipe their stdin and stdout, and input data from a reader to the process. The stdin of the first process will be set to `Stdio::piped()`. By default, the stdout and stderr of the last process are inherited from the parent.
     fn execute_multiple_input_reader_output2<const N: usize>(
         &mut self,
         reader: &mut dyn Read,
         others: &mut [&mut Command],
-    ) -> Result<Output, io::Error>;
-}
+    ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_reader_output2::<N>(reader);
+        }
 
-impl Execute for Command {
-    #[inline]
-    fn execute(&mut self) -> Result<Option<i32>, io::Error> {
-        self.stdout(Stdio::null());
+        self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        Ok(self.status()?.code())
-    }
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
-    #[inline]
-    fn execute_output(&mut self) -> Result<Output, io::Error> {
-        self.spawn()?.wait_with_output()
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait_with_output(),
+            |stdin| copy_reader_to_stdin::<N>(stdin, reader),
+        )
     }
 
-    #[inline]
-    fn execute_input<D: ?Sized + AsRef<[u8]>>(
+    fn execute_multiple_input_reader_with_capacity(
         &mut self,
-        data: &D,
+        reader: &mut dyn Read,
+        capacity: usize,
+        others: &mut [&mut Command],
     ) -> Result<Option<i32>, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_reader_with_capacity(reader, capacity);
+        }
+
+        check_reader_capacity(capacity)?;
+
         self.stdin(Stdio::piped());
-        self.stdout(Stdio::null());
+        self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let mut child = self.spawn()?;
-        let stdin = take_child_stdin(&mut child)?;
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
         wait_with_stdin_writer(
-            move || child.wait().map(|status| status.code()),
-            || write_stdin(stdin, data),
+            stdin,
+            &kill_handles,
+            move || pipeline.wait(),
+            |stdin| copy_reader_to_stdin_with_capacity(stdin, reader, capacity),
         )
     }
 
-    #[inline]
-    fn execute_input_output<D: ?Sized + AsRef<[u8]>>(
+    fn execute_multiple_input_reader_output_with_capacity(
         &mut self,
-        data: &D,
+        reader: &mut dyn Read,
+        capacity: usize,
+        others: &mut [&mut Command],
     ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_reader_output_with_capacity(reader, capacity);
+        }
+
+        check_reader_capacity(capacity)?;
+
         self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
 
-        let mut child = self.spawn()?;
-        let stdin = take_child_stdin(&mut child)?;
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
-        wait_with_stdin_writer(move || child.wait_with_output(), || write_stdin(stdin, data))
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait_with_output(),
+            |stdin| copy_reader_to_stdin_with_capacity(stdin, reader, capacity),
+        )
     }
 
-    #[inline]
-    fn execute_input_reader2<const N: usize>(
+    fn execute_multiple_input_buf_reader(
         &mut self,
-        reader: &mut dyn Read,
+        reader: &mut dyn BufRead,
+        others: &mut [&mut Command],
     ) -> Result<Option<i32>, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_buf_reader(reader);
+        }
+
         self.stdin(Stdio::piped());
-        self.stdout(Stdio::null());
+        self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let mut child = self.spawn()?;
-        let stdin = take_child_stdin(&mut child)?;
+        let others_length_dec = others.len() - 1;
+        let last_other = &mut others[others_length_dec];
+
+        last_other.stdout(Stdio::null());
+        last_other.stderr(Stdio::null());
+
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
         wait_with_stdin_writer(
-            move || child.wait().map(|status| status.code()),
-            || copy_reader_to_stdin::<N>(stdin, reader),
+            stdin,
+            &kill_handles,
+            move || pipeline.wait(),
+            |stdin| copy_buf_reader_to_stdin(stdin, reader),
         )
     }
 
-    #[inline]
-    fn execute_input_reader_output2<const N: usize>(
+    fn execute_multiple_input_buf_reader_output(
         &mut self,
-        reader: &mut dyn Read,
+        reader: &mut dyn BufRead,
+        others: &mut [&mut Command],
     ) -> Result<Output, io::Error> {
+        if others.is_empty() {
+            return self.execute_input_buf_reader_output(reader);
+        }
+
         self.stdin(Stdio::piped());
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
 
-        let mut child = self.spawn()?;
-        let stdin = take_child_stdin(&mut child)?;
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
         wait_with_stdin_writer(
-            move || child.wait_with_output(),
-            || copy_reader_to_stdin::<N>(stdin, reader),
+            stdin,
+            &kill_handles,
+            move || pipeline.wait_with_output(),
+            |stdin| copy_buf_reader_to_stdin(stdin, reader),
         )
     }
 
-    fn execute_multiple(&mut self, others: &mut [&mut Command]) -> Result<Option<i32>, io::Error> {
+    fn execute_multiple_input_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        others: &mut [&mut Command],
+    ) -> Result<Option<i32>, io::Error> {
         if others.is_empty() {
-            return self.execute();
+            return self.execute_input_file(path);
         }
 
+        self.stdin(File::open(path)?);
         self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
@@ -678,36 +2193,42 @@ impl Execute for Command {
         last_other.stdout(Stdio::null());
         last_other.stderr(Stdio::null());
 
-        let (upstream_children, mut last_child) = spawn_pipeline(self, others)?;
-        let status_result = last_child.wait().map(|status| status.code());
+        let pipeline = Pipeline::spawn(self, others, false);
+
+        self.stdin(Stdio::inherit());
 
-        finish_pipeline_result(status_result, upstream_children)
+        pipeline?.wait()
     }
 
-    fn execute_multiple_output(
+    fn execute_multiple_input_file_output<P: AsRef<Path>>(
         &mut self,
+        path: P,
         others: &mut [&mut Command],
     ) -> Result<Output, io::Error> {
         if others.is_empty() {
-            return self.execute_output();
+            return self.execute_input_file_output(path);
         }
 
+        self.stdin(File::open(path)?);
         self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let (upstream_children, last_child) = spawn_pipeline(self, others)?;
-        let output_result = last_child.wait_with_output();
+        let pipeline = Pipeline::spawn(self, others, false);
 
-        finish_pipeline_result(output_result, upstream_children)
+        self.stdin(Stdio::inherit());
+
+        pipeline?.wait_with_output()
     }
 
-    fn execute_multiple_input<D: ?Sized + AsRef<[u8]>>(
+    fn execute_multiple_input_writer<F>(
         &mut self,
-        data: &D,
+        write: F,
         others: &mut [&mut Command],
-    ) -> Result<Option<i32>, io::Error> {
+    ) -> Result<Option<i32>, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>, {
         if others.is_empty() {
-            return self.execute_input(data);
+            return self.execute_input_writer(write);
         }
 
         self.stdin(Stdio::piped());
@@ -720,89 +2241,119 @@ impl Execute for Command {
         last_other.stdout(Stdio::null());
         last_other.stderr(Stdio::null());
 
-        let (mut upstream_children, mut last_child) = spawn_pipeline(self, others)?;
-        let stdin = take_child_stdin(&mut upstream_children[0])?;
-        let status_result = wait_with_stdin_writer(
-            move || last_child.wait().map(|status| status.code()),
-            || write_stdin(stdin, data),
-        );
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
-        finish_pipeline_result(status_result, upstream_children)
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait(),
+            |stdin| write_stdin_with(stdin, write),
+        )
     }
 
-    fn execute_multiple_input_output<D: ?Sized + AsRef<[u8]>>(
+    fn execute_multiple_input_writer_output<F>(
         &mut self,
-        data: &D,
+        write: F,
         others: &mut [&mut Command],
-    ) -> Result<Output, io::Error> {
+    ) -> Result<Output, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>, {
         if others.is_empty() {
-            return self.execute_input_output(data);
+            return self.execute_input_writer_output(write);
         }
 
         self.stdin(Stdio::piped());
         self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let (mut upstream_children, last_child) = spawn_pipeline(self, others)?;
-        let stdin = take_child_stdin(&mut upstream_children[0])?;
-        let output_result = wait_with_stdin_writer(
-            move || last_child.wait_with_output(),
-            || write_stdin(stdin, data),
-        );
+        let mut pipeline = Pipeline::spawn(self, others, false)?;
+        let stdin = take_child_stdin(&mut pipeline.upstream_children_mut()[0])?;
+        let kill_handles = pipeline.kill_handles()?;
 
-        finish_pipeline_result(output_result, upstream_children)
+        wait_with_stdin_writer(
+            stdin,
+            &kill_handles,
+            move || pipeline.wait_with_output(),
+            |stdin| write_stdin_with(stdin, write),
+        )
     }
 
-    fn execute_multiple_input_reader2<const N: usize>(
+    fn execute_multiple_stages(
         &mut self,
-        reader: &mut dyn Read,
-        others: &mut [&mut Command],
+        mut stages: Vec<Stage<'_>>,
     ) -> Result<Option<i32>, io::Error> {
-        if others.is_empty() {
-            return self.execute_input_reader2::<N>(reader);
+        if stages.is_empty() {
+            return self.execute();
         }
 
-        self.stdin(Stdio::piped());
         self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let others_length_dec = others.len() - 1;
-        let last_other = &mut others[others_length_dec];
+        if let Some(Stage::Command(last_command)) = stages.last_mut() {
+            last_command.stdout(Stdio::null());
+            last_command.stderr(Stdio::null());
+        }
 
-        last_other.stdout(Stdio::null());
-        last_other.stderr(Stdio::null());
+        stage::execute_stages(self, stages, Pipeline::wait)
+    }
+
+    fn execute_multiple_stages_output(
+        &mut self,
+        stages: Vec<Stage<'_>>,
+    ) -> Result<Output, io::Error> {
+        if stages.is_empty() {
+            return self.execute_output();
+        }
 
-        let (mut upstream_children, mut last_child) = spawn_pipeline(self, others)?;
-        let stdin = take_child_stdin(&mut upstream_children[0])?;
-        let status_result = wait_with_stdin_writer(
-            move || last_child.wait().map(|status| status.code()),
-            || copy_reader_to_stdin::<N>(stdin, reader),
-        );
+        self.stdout(Stdio::piped());
+        self.stderr(Stdio::null());
 
-        finish_pipeline_result(status_result, upstream_children)
+        stage::execute_stages(self, stages, Pipeline::wait_with_output)
     }
 
-    fn execute_multiple_input_reader_output2<const N: usize>(
+    #[inline]
+    fn execute_batches<I, S>(
+        &mut self,
+        args: I,
+        options: BatchOptions,
+    ) -> Result<Vec<BatchReport>, io::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>, {
+        let base = &*self;
+
+        batch::execute_batches(|| batch::copy_command(base), args, options)
+    }
+
+    #[inline]
+    fn execute_tee_output(
+        &mut self,
+        branches: &mut [&mut Command],
+    ) -> Result<TeeOutput, io::Error> {
+        self.stdout(Stdio::piped());
+
+        tee::execute_tee(Pipeline::spawn(self, &mut [], false)?, branches)
+    }
+
+    fn execute_multiple_tee_output(
         &mut self,
-        reader: &mut dyn Read,
         others: &mut [&mut Command],
-    ) -> Result<Output, io::Error> {
+        branches: &mut [&mut Command],
+    ) -> Result<TeeOutput, io::Error> {
         if others.is_empty() {
-            return self.execute_input_reader_output2::<N>(reader);
+            return self.execute_tee_output(branches);
         }
 
-        self.stdin(Stdio::piped());
         self.stdout(Stdio::piped());
         self.stderr(Stdio::null());
 
-        let (mut upstream_children, last_child) = spawn_pipeline(self, others)?;
-        let stdin = take_child_stdin(&mut upstream_children[0])?;
-        let output_result = wait_with_stdin_writer(
-            move || last_child.wait_with_output(),
-            || copy_reader_to_stdin::<N>(stdin, reader),
-        );
+        let others_length_dec = others.len() - 1;
+
+        others[others_length_dec].stdout(Stdio::piped());
 
-        finish_pipeline_result(output_result, upstream_children)
+        tee::execute_tee(Pipeline::spawn(self, others, false)?, branches)
     }
 }
 
@@ -836,12 +2387,8 @@ pub fn shell<S: AsRef<OsStr>>(cmd: S) -> Command {
     command
 }
 
-/// Create a `Command` instance by parsing a command string.
-#[inline]
-pub fn command<S: AsRef<str>>(cmd: S) -> Command {
-    let tokens = command_tokens(cmd);
-
-    if tokens.is_empty() {
+fn command_from_tokens<T: AsRef<OsStr>>(envs: Vec<(String, String)>, tokens: Vec<T>) -> Command {
+    let mut command = if tokens.is_empty() {
         Command::new("")
     } else {
         let mut command = Command::new(&tokens[0]);
@@ -849,5 +2396,79 @@ pub fn command<S: AsRef<str>>(cmd: S) -> Command {
         command.args(&tokens[1..]);
 
         command
-    }
+    };
+
+    command.envs(envs);
+
+    command
+}
+
+#[inline]
+fn command_from_parsed_tokens(tokens: Vec<Token>) -> Command {
+    let (envs, tokens) = split_env_assignments(tokens);
+
+    command_from_tokens(envs, tokens)
+}
+
+/// Create a `Command` instance by parsing a command string. Leading `NAME=value` tokens are set as environment variables of the command.
+#[inline]
+pub fn command<S: AsRef<str>>(cmd: S) -> Command {
+    command_with_options(cmd, ParseOptions::new())
+}
+
+/// Create a `Command` instance by parsing a command string with the given options. Leading `NAME=value` tokens are set as environment variables of the command.
+#[inline]
+pub fn command_with_options<S: AsRef<str>>(cmd: S, options: ParseOptions) -> Command {
+    command_from_parsed_tokens(tokenize(cmd, options))
+}
+
+/// Create a `Command` instance by parsing a command string and expanding the unquoted `*`, `?`, `[...]` and `**` patterns of its tokens against `dir`, which is also set as the working directory of the command. Quoted patterns stay literal. Leading `NAME=value` tokens are set as environment variables of the command and are not expanded.
+#[inline]
+pub fn command_with_glob<S: AsRef<str>, P: AsRef<Path>>(
+    cmd: S,
+    options: ParseOptions,
+    dir: P,
+    policy: NoMatchPolicy,
+) -> Result<Command, io::Error> {
+    let dir = dir.as_ref();
+    let (envs, tokens) = split_env_assignments(tokenize(cmd, options));
+    let tokens = expand_globs(tokens, dir, policy)?;
+
+    let mut command = command_from_tokens(envs, tokens);
+
+    command.current_dir(dir);
+
+    Ok(command)
+}
+
+/// Create `Command` instances by parsing a multi-line script, one command per logical line. Blank lines and comment-only lines are skipped. Leading `NAME=value` tokens of each line are set as environment variables of that command.
+#[inline]
+pub fn commands<S: AsRef<str>>(script: S, options: ParseOptions) -> Vec<Command> {
+    tokenize_script(script, options).into_iter().map(command_from_parsed_tokens).collect()
+}
+
+/// Create a `Command` instance by parsing a command string, along with the stdin data given by a `<<<word` here-string or a `<<DELIMITER` here-document. Here-documents are enabled regardless of `options`. The data can be fed by the `execute_input` or `execute_input_output` method.
+#[inline]
+pub fn command_with_input<S: AsRef<str>>(
+    cmd: S,
+    options: ParseOptions,
+) -> (Command, Option<String>) {
+    let ParsedCommand {
+        tokens,
+        input,
+    } = parse_command(cmd, options.here_documents(true));
+
+    (command_from_parsed_tokens(tokens), input)
+}
+
+/// Create `Command` instances by parsing a multi-line script, one command per logical line, along with the stdin data given by here-strings or here-documents. Here-documents are enabled regardless of `options`.
+#[inline]
+pub fn commands_with_input<S: AsRef<str>>(
+    script: S,
+    options: ParseOptions,
+) -> Vec<(Command, Option<String>)> {
+    parse_script(script, options.here_documents(true))
+        .into_iter()
+        .map(|command| (command_from_parsed_tokens(command.tokens), command.input))
+        .collect()
 }
@@ -0,0 +1,67 @@
+use std::{fs::File, io};
+
+/// Create an anonymous pipe and return its read end and write end, like `std::io::pipe`, which needs a newer Rust than this crate supports. Neither end is inherited by child processes unless it is given to them as stdio.
+#[cfg(unix)]
+pub(crate) fn pipe() -> Result<(File, File), io::Error> {
+    #[cfg(target_vendor = "apple")]
+    use std::os::fd::AsRawFd;
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let mut fds = [0; 2];
+
+    // SAFETY: `fds` is valid for writes of two descriptors.
+    #[cfg(not(target_vendor = "apple"))]
+    let result = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) };
+
+    // SAFETY: `fds` is valid for writes of two descriptors.
+    #[cfg(target_vendor = "apple")]
+    let result = unsafe { libc::pipe(fds.as_mut_ptr()) };
+
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: The descriptors have just been created and are owned by nothing else.
+    let (reader, writer) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    // Apple platforms have no `pipe2`, so the ends are marked as close-on-exec after creating them.
+    #[cfg(target_vendor = "apple")]
+    for fd in [&reader, &writer] {
+        // SAFETY: `fcntl` has no memory safety requirements.
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok((reader.into(), writer.into()))
+}
+
+/// Create an anonymous pipe. See the Unix version.
+#[cfg(windows)]
+pub(crate) fn pipe() -> Result<(File, File), io::Error> {
+    use std::{
+        os::windows::io::{FromRawHandle, OwnedHandle},
+        ptr,
+    };
+
+    use windows_sys::Win32::System::Pipes::CreatePipe;
+
+    let mut reader = ptr::null_mut();
+    let mut writer = ptr::null_mut();
+
+    // SAFETY: Both handles are valid for writes. Without security attributes, the handles are not inheritable.
+    if unsafe { CreatePipe(&mut reader, &mut writer, ptr::null(), 0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: The handles have just been created and are owned by nothing else.
+    let (reader, writer) =
+        unsafe { (OwnedHandle::from_raw_handle(reader), OwnedHandle::from_raw_handle(writer)) };
+
+    Ok((reader.into(), writer.into()))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn pipe() -> Result<(File, File), io::Error> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "pipes are not supported on this platform"))
+}
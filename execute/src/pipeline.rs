@@ -0,0 +1,413 @@
+use std::{
+    io::{self, Write},
+    mem,
+    process::{Command, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{os::unix::process::CommandExt, process::Child};
+
+#[cfg(unix)]
+use crate::ProcessGroup;
+use crate::{
+    ChildGuard, ProcessStatus, Termination, TerminationPolicy,
+    guard::KillHandle,
+    take_child_stdout,
+    termination::{POLL_INTERVAL, terminate_children},
+};
+
+#[cfg(unix)]
+pub(crate) type ProcessGroupHandle = ProcessGroup;
+
+/// Process groups are only supported on Unix, so no value of this type can exist on other platforms.
+#[cfg(not(unix))]
+pub(crate) type ProcessGroupHandle = std::convert::Infallible;
+
+/// The spawned processes of a pipeline, or of a single command.
+///
+/// If the pipeline is dropped without being waited for, including during panic unwinding, every process, as well as its process group if it has one, is killed with `SIGKILL` and reaped, so dropping never blocks for a grace period. Call [`terminate`](Self::terminate) first to end the processes gracefully according to the [`TerminationPolicy`]. When a stage cannot be spawned, or the last process cannot be waited for, the other processes are ended according to the termination policy as well.
+///
+/// The process group is only signaled while at least one process of the pipeline has not been reaped, since the group ID can be reused by the system after that. Once every process has been waited for, the processes which they forked and left in the group are no longer managed by the pipeline.
+#[derive(Debug)]
+pub struct Pipeline {
+    upstream_children:  Vec<ChildGuard>,
+    last_child:         ChildGuard,
+    process_group:      Option<ProcessGroupHandle>,
+    termination_policy: TerminationPolicy,
+}
+
+impl Pipeline {
+    pub(crate) fn spawn(
+        first: &mut Command,
+        others: &mut [&mut Command],
+        in_process_group: bool,
+    ) -> Result<Self, io::Error> {
+        #[cfg(unix)]
+        let first_child = spawn_in_process_group(first, in_process_group.then_some(0))?;
+
+        #[cfg(not(unix))]
+        let first_child = {
+            let _ = in_process_group;
+
+            first.spawn()?
+        };
+
+        // The first process is the leader of the new process group, which the other processes join.
+        #[cfg(unix)]
+        let process_group = in_process_group.then(|| ProcessGroup::new(first_child.id()));
+
+        #[cfg(not(unix))]
+        let process_group = None;
+
+        let mut pipeline = Self {
+            upstream_children: Vec::with_capacity(others.len()),
+            last_child: ChildGuard::new(first_child),
+            process_group,
+            termination_policy: TerminationPolicy::new(),
+        };
+
+        if let Err(err) = pipeline.spawn_stages(others) {
+            // The processes spawned so far are ended like the ones of a failed pipeline, rather than killed right away by dropping it.
+            let _ = pipeline.terminate();
+
+            return Err(err);
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Spawn `others` after the processes of this pipeline, each one reading the stdout of the previous one.
+    fn spawn_stages(&mut self, others: &mut [&mut Command]) -> Result<(), io::Error> {
+        let last_index = others.len().saturating_sub(1);
+
+        for (index, other) in others.iter_mut().enumerate() {
+            // Each child becomes upstream as soon as its stdout is moved into the next command's stdin.
+            let stdout = take_child_stdout(&mut self.last_child)?;
+
+            other.stdin(stdout);
+
+            if index < last_index {
+                other.stdout(Stdio::piped());
+                other.stderr(Stdio::null());
+            }
+
+            #[cfg(unix)]
+            let child = spawn_in_process_group(other, self.process_group.map(|group| group.id()));
+
+            #[cfg(not(unix))]
+            let child = other.spawn();
+
+            // The `Command` keeps the read end of the pipe open until its stdin is replaced, so the previous process would never get `SIGPIPE` after this one exits.
+            other.stdin(Stdio::inherit());
+
+            let child = ChildGuard::new(child?);
+
+            // Keep the last child separate because callers need its status or captured output as the method result.
+            let previous_child = mem::replace(&mut self.last_child, child);
+
+            self.upstream_children.push(previous_child);
+        }
+
+        Ok(())
+    }
+
+    /// Create a pipeline of processes which have already been spawned and connected, in pipeline order. `children` must not be empty.
+    pub(crate) fn from_children(mut children: Vec<ChildGuard>) -> Self {
+        let last_child = children.pop().expect("a pipeline needs at least one process");
+
+        Self {
+            upstream_children: children,
+            last_child,
+            process_group: None,
+            termination_policy: TerminationPolicy::new(),
+        }
+    }
+
+    /// Get the processes of every stage except the last one, in pipeline order.
+    #[inline]
+    pub fn upstream_children(&self) -> &[ChildGuard] {
+        &self.upstream_children
+    }
+
+    /// Get the processes of every stage except the last one, in pipeline order.
+    #[inline]
+    pub fn upstream_children_mut(&mut self) -> &mut [ChildGuard] {
+        &mut self.upstream_children
+    }
+
+    /// Get the process of the last stage.
+    #[inline]
+    pub fn last_child(&self) -> &ChildGuard {
+        &self.last_child
+    }
+
+    /// Get the process of the last stage, for example, to take its piped stdout.
+    #[inline]
+    pub fn last_child_mut(&mut self) -> &mut ChildGuard {
+        &mut self.last_child
+    }
+
+    /// Get a kill handle of every process of the pipeline. See [`ChildGuard::kill_handle`].
+    pub(crate) fn kill_handles(&mut self) -> Result<Vec<KillHandle>, io::Error> {
+        self.upstream_children
+            .iter_mut()
+            .chain([&mut self.last_child])
+            .map(ChildGuard::kill_handle)
+            .collect()
+    }
+
+    /// Get the process group of the pipeline, if it was spawned in its own process group.
+    #[cfg(unix)]
+    #[inline]
+    pub fn process_group(&self) -> Option<ProcessGroup> {
+        self.process_group
+    }
+
+    /// Set how the processes are terminated by [`terminate`](Self::terminate).
+    #[inline]
+    pub fn set_termination_policy(&mut self, termination_policy: TerminationPolicy) {
+        self.termination_policy = termination_policy;
+    }
+
+    /// Terminate every process of the pipeline according to the termination policy and reap them. If the pipeline has its own process group, the signals are sent to the whole group. Returns which step ended the processes.
+    pub fn terminate(&mut self) -> Result<Termination, io::Error> {
+        let mut children: Vec<&mut ChildGuard> =
+            self.upstream_children.iter_mut().chain([&mut self.last_child]).collect();
+
+        let termination =
+            terminate_children(&mut children, self.process_group, self.termination_policy)?;
+
+        // The whole group has been signaled, so it is not signaled again when this pipeline is dropped.
+        self.process_group = None;
+
+        Ok(termination)
+    }
+
+    /// Wait for every process of the pipeline to exit for at most `timeout`, and get the exit status of the last one. Returns `None` if any process is still running, for example, to [`terminate`](Self::terminate) them.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>, io::Error> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let mut last_status = None;
+            let mut all_exited = true;
+
+            for child in self.upstream_children.iter_mut().chain([&mut self.last_child]) {
+                match child.try_wait()? {
+                    Some(status) => last_status = Some(status),
+                    None => all_exited = false,
+                }
+            }
+
+            if all_exited {
+                // Every process has been reaped, so the group ID may be reused and must not be signaled any more.
+                self.process_group = None;
+
+                return Ok(last_status);
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Kill every process of the pipeline. If the pipeline has its own process group, the whole group is killed, including the processes forked by the stages.
+    pub fn kill(&mut self) -> Result<(), io::Error> {
+        #[cfg(unix)]
+        if let Some(process_group) = self.release_reaped_process_group()? {
+            match process_group.kill() {
+                Ok(()) => return Ok(()),
+                // Every process of the group has been reaped, so only the children themselves are left to check.
+                Err(err) if err.raw_os_error() == Some(libc::ESRCH) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut first_error = None;
+
+        for child in self.upstream_children.iter_mut().chain([&mut self.last_child]) {
+            if let Err(err) = child.kill() {
+                first_error.get_or_insert(err);
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for every process of the pipeline to exit, and get the exit status code of the last one.
+    #[inline]
+    pub fn wait(self) -> Result<Option<i32>, io::Error> {
+        self.wait_status().map(|status| status.code())
+    }
+
+    /// Wait for every process of the pipeline to exit, and get the exit status of the last one, including the signal which terminated it.
+    #[inline]
+    pub fn wait_status(mut self) -> Result<ProcessStatus, io::Error> {
+        let status_result = self.last_child.wait().map(ProcessStatus::from);
+
+        self.finish(status_result)
+    }
+
+    /// Wait for every process of the pipeline to exit, and collect the output of the last one.
+    #[inline]
+    pub fn wait_with_output(mut self) -> Result<Output, io::Error> {
+        let output_result = self.last_child.wait_with_output();
+
+        self.finish(output_result)
+    }
+
+    /// Wait for every process of the pipeline to exit, while copying the stdout of the last one into `writer`. See [`ChildGuard::wait_with_output_to_writer`].
+    #[inline]
+    pub fn wait_with_output_to_writer(
+        mut self,
+        writer: &mut dyn Write,
+    ) -> Result<Output, io::Error> {
+        let output_result = self.last_child.wait_with_output_to_writer(writer);
+
+        self.finish(output_result)
+    }
+
+    /// Wait for every process of the pipeline to exit and reap them with `wait4`, in order to get the resources they used. Returns the status of every process in pipeline order.
+    #[cfg(target_os = "linux")]
+    pub fn wait_with_resource_usage(mut self) -> Result<Vec<ProcessStatus>, io::Error> {
+        let statuses = self
+            .upstream_children
+            .iter_mut()
+            .chain([&mut self.last_child])
+            .map(|child| child.wait_with_resource_usage())
+            .collect::<Result<Vec<ProcessStatus>, io::Error>>()?;
+
+        // The pipeline has finished normally, so the processes left in its group are not killed when it is dropped.
+        self.process_group = None;
+
+        Ok(statuses)
+    }
+
+    /// Get the process group while any process of the pipeline has not been reaped, which keeps the group ID from being reused. Otherwise, forget the group.
+    #[cfg(unix)]
+    fn release_reaped_process_group(&mut self) -> Result<Option<ProcessGroup>, io::Error> {
+        if self.process_group.is_some() {
+            let mut all_reaped = true;
+
+            for child in self.upstream_children.iter_mut().chain([&mut self.last_child]) {
+                if child.try_wait()?.is_none() {
+                    all_reaped = false;
+                }
+            }
+
+            if all_reaped {
+                self.process_group = None;
+            }
+        }
+
+        Ok(self.process_group)
+    }
+
+    /// Wait for every process of the pipeline to exit and collect the output of the last one like [`wait_with_output`](Self::wait_with_output), but reap them with `wait4` like [`wait_with_resource_usage`](Self::wait_with_resource_usage). Returns the output along with the status of every process in pipeline order.
+    #[cfg(target_os = "linux")]
+    pub fn wait_with_output_and_resource_usage(
+        mut self,
+    ) -> Result<(Output, Vec<ProcessStatus>), io::Error> {
+        let (stdout, stderr) = self.last_child.read_output();
+
+        let statuses = self
+            .upstream_children
+            .iter_mut()
+            .chain([&mut self.last_child])
+            .map(|child| child.wait_with_resource_usage())
+            .collect::<Result<Vec<ProcessStatus>, io::Error>>()?;
+
+        // The pipeline has finished normally, so the processes left in its group are not killed when it is dropped.
+        self.process_group = None;
+
+        let output = Output {
+            status: statuses[statuses.len() - 1].exit_status(),
+            stdout: stdout?,
+            stderr: stderr?,
+        };
+
+        Ok((output, statuses))
+    }
+
+    fn finish<T>(&mut self, result: Result<T, io::Error>) -> Result<T, io::Error> {
+        if result.is_err() {
+            // The last process has failed to be waited for, so the others are ended according to the termination policy instead of being waited for.
+            let _ = self.terminate();
+
+            return result;
+        }
+
+        // The public result follows normal shell pipeline behavior and comes from the last command, but upstream children still must be reaped.
+        let upstream_result = self.wait_upstream_children();
+
+        // The pipeline has finished normally, so the processes left in its group are not killed when it is dropped.
+        self.process_group = None;
+
+        match result {
+            Ok(value) => {
+                upstream_result?;
+
+                Ok(value)
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    fn wait_upstream_children(&mut self) -> Result<(), io::Error> {
+        let mut first_error = None;
+
+        for child in &mut self.upstream_children {
+            if let Err(err) = child.wait() {
+                if first_error.is_none() {
+                    first_error = Some(err);
+                }
+            }
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    #[inline]
+    fn drop(&mut self) {
+        let mut children: Vec<&mut ChildGuard> =
+            self.upstream_children.iter_mut().chain([&mut self.last_child]).collect();
+
+        // Nothing is signaled if the pipeline has already been waited for.
+        let _ = terminate_children(&mut children, self.process_group, TerminationPolicy::kill());
+    }
+}
+
+/// Spawn `command` into `process_group`, or into a new process group led by the process itself if the ID is 0.
+///
+/// A `Command` cannot be copied together with its stdio and `pre_exec` closures, so the group is set on the caller's `Command` by `CommandExt::process_group`, which replaces the previous setting instead of adding up like `pre_exec` closures. Afterwards, it is set to the process group of this process, which is where a child is put by default.
+#[cfg(unix)]
+fn spawn_in_process_group(
+    command: &mut Command,
+    process_group_id: Option<u32>,
+) -> Result<Child, io::Error> {
+    let Some(process_group_id) = process_group_id else {
+        return command.spawn();
+    };
+
+    command.process_group(process_group_id as libc::pid_t);
+
+    let child = command.spawn();
+
+    // SAFETY: `getpgrp` has no memory safety requirements and always succeeds.
+    command.process_group(unsafe { libc::getpgrp() });
+
+    child
+}
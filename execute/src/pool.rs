@@ -0,0 +1,410 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, ErrorKind, Write},
+    iter::Fuse,
+    num::NonZeroUsize,
+    process::{Command, ExitStatus, Output, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(not(unix))]
+use crate::termination::POLL_INTERVAL;
+use crate::{
+    ChildGuard, TerminationPolicy,
+    guard::{KillHandle, read_to_end},
+};
+
+/// A command executed by a [`JobPool`], with optional data for its stdin.
+#[derive(Debug)]
+pub struct Job {
+    command: Command,
+    input:   Option<Vec<u8>>,
+}
+
+impl Job {
+    /// Create a job which executes `command`. By default, stdin, stdout and stderr are inherited from the parent, so set stdout and stderr to `Stdio::piped()` to capture them.
+    #[inline]
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            input: None,
+        }
+    }
+
+    /// Input in-memory data to the process. stdin will be set to `Stdio::piped()`.
+    #[inline]
+    pub fn input<D: Into<Vec<u8>>>(mut self, data: D) -> Self {
+        self.input = Some(data.into());
+
+        self
+    }
+}
+
+impl From<Command> for Job {
+    #[inline]
+    fn from(command: Command) -> Self {
+        Self::new(command)
+    }
+}
+
+/// The order in which a [`JobPool`] yields the results of its jobs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum JobOrder {
+    /// Yield each result as soon as its job finishes.
+    #[default]
+    Completion,
+    /// Yield the results in the order the jobs were given.
+    Input,
+}
+
+/// The result of a job of a [`JobPool`].
+#[derive(Debug)]
+pub struct JobReport {
+    /// The position of the job in the given jobs.
+    pub index:   usize,
+    /// The output of the job, or the error which happened while spawning or waiting for it.
+    pub result:  Result<Output, io::Error>,
+    /// How long the job ran.
+    pub elapsed: Duration,
+}
+
+impl JobReport {
+    /// Whether the process of the job ran and exited with status code 0.
+    #[inline]
+    pub fn success(&self) -> bool {
+        matches!(&self.result, Ok(output) if output.status.success())
+    }
+}
+
+/// Runs many independent commands in parallel, like `xargs -P`.
+#[derive(Debug, Clone)]
+pub struct JobPool {
+    concurrency:        usize,
+    order:              JobOrder,
+    fail_fast:          bool,
+    termination_policy: TerminationPolicy,
+}
+
+impl JobPool {
+    /// Create a pool which runs as many jobs as the available parallelism at once, yields the results in completion order, and keeps going after a job fails.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            concurrency:        thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            order:              JobOrder::default(),
+            fail_fast:          false,
+            termination_policy: TerminationPolicy::new(),
+        }
+    }
+
+    /// Set how many jobs can run at once. Zero is treated as one.
+    #[inline]
+    pub fn set_concurrency(&mut self, concurrency: usize) {
+        self.concurrency = concurrency.max(1);
+    }
+
+    /// Set the order in which the results are yielded.
+    #[inline]
+    pub fn set_order(&mut self, order: JobOrder) {
+        self.order = order;
+    }
+
+    /// Stop after the first job which fails or does not exit successfully. No more jobs are taken from the iterator, and the processes which are still running are terminated according to the termination policy.
+    #[inline]
+    pub fn set_fail_fast(&mut self, fail_fast: bool) {
+        self.fail_fast = fail_fast;
+    }
+
+    /// Set how running processes are terminated when the pool fails fast or the results are dropped.
+    #[inline]
+    pub fn set_termination_policy(&mut self, termination_policy: TerminationPolicy) {
+        self.termination_policy = termination_policy;
+    }
+
+    /// Start running `jobs`. The jobs are taken from the iterator only when there is room for them to run, and each one is run on its own thread. Dropping the returned iterator terminates the processes which are still running according to the termination policy, and waits for their threads to finish.
+    pub fn run<I>(&self, jobs: I) -> JobResults<I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Into<Job>, {
+        let (sender, receiver) = mpsc::channel();
+
+        JobResults {
+            pool: self.clone(),
+            jobs: jobs.into_iter().fuse(),
+            sender,
+            receiver,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            started: 0,
+            running: 0,
+            threads: HashMap::new(),
+            kill_deadline: None,
+            finished: BTreeMap::new(),
+            next_index: 0,
+        }
+    }
+}
+
+impl Default for JobPool {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator over the results of the jobs run by a [`JobPool`].
+#[derive(Debug)]
+pub struct JobResults<I> {
+    pool:          JobPool,
+    jobs:          Fuse<I>,
+    sender:        Sender<JobReport>,
+    receiver:      Receiver<JobReport>,
+    cancelled:     Arc<AtomicBool>,
+    started:       usize,
+    running:       usize,
+    /// The threads of the running jobs, with handles to kill their processes, by the indexes of the jobs.
+    threads:       HashMap<usize, (JoinHandle<()>, KillHandle)>,
+    /// When the running processes are killed after they have been sent the termination signal.
+    kill_deadline: Option<Instant>,
+    /// The results which wait for the ones before them, in input order.
+    finished:      BTreeMap<usize, JobReport>,
+    next_index:    usize,
+}
+
+impl<I> Iterator for JobResults<I>
+where
+    I: Iterator,
+    I::Item: Into<Job>,
+{
+    type Item = JobReport;
+
+    fn next(&mut self) -> Option<JobReport> {
+        loop {
+            if let Some(report) = self.finished.remove(&self.next_index) {
+                self.next_index += 1;
+
+                return Some(report);
+            }
+
+            while !self.cancelled.load(Ordering::Relaxed) && self.running < self.pool.concurrency {
+                match self.jobs.next() {
+                    Some(job) => self.start(job.into()),
+                    None => break,
+                }
+            }
+
+            if self.running == 0 {
+                return None;
+            }
+
+            let report = self.receive();
+
+            self.running -= 1;
+
+            if let Some((thread, _)) = self.threads.remove(&report.index) {
+                // The thread has sent its report, so it is about to finish.
+                let _ = thread.join();
+            }
+
+            if self.pool.fail_fast && !report.success() {
+                self.cancel();
+            }
+
+            match self.pool.order {
+                JobOrder::Completion => return Some(report),
+                JobOrder::Input => {
+                    self.finished.insert(report.index, report);
+                },
+            }
+        }
+    }
+}
+
+impl<I> JobResults<I> {
+    fn start(&mut self, job: Job) {
+        let index = self.started;
+        let sender = self.sender.clone();
+        let started = Instant::now();
+
+        self.started += 1;
+        self.running += 1;
+
+        let Job {
+            mut command,
+            input,
+        } = job;
+
+        if input.is_some() {
+            command.stdin(Stdio::piped());
+        }
+
+        // The process is spawned here, so that this iterator can kill it without waiting for it.
+        let child = command.spawn().map(ChildGuard::new).and_then(|mut child| {
+            let kill_handle = child.kill_handle()?;
+
+            Ok((child, kill_handle))
+        });
+
+        let (mut child, kill_handle) = match child {
+            Ok(child) => child,
+            Err(err) => {
+                // This iterator keeps the receiver alive.
+                sender
+                    .send(JobReport {
+                        index,
+                        result: Err(err),
+                        elapsed: started.elapsed(),
+                    })
+                    .unwrap();
+
+                return;
+            },
+        };
+
+        child.set_termination_policy(self.pool.termination_policy);
+
+        let cancelled = Arc::clone(&self.cancelled);
+
+        let thread = thread::spawn(move || {
+            let result = run_job(child, input, &cancelled);
+
+            // The results may have been dropped.
+            let _ = sender.send(JobReport {
+                index,
+                result,
+                elapsed: started.elapsed(),
+            });
+        });
+
+        self.threads.insert(index, (thread, kill_handle));
+    }
+
+    /// Receive the next report. Every running job sends exactly one report, and this iterator keeps a sender alive.
+    fn receive(&mut self) -> JobReport {
+        if let Some(deadline) = self.kill_deadline {
+            if let Ok(report) =
+                self.receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                return report;
+            }
+
+            self.kill_running();
+        }
+
+        self.receiver.recv().unwrap()
+    }
+
+    /// Stop starting jobs, and send the termination signal to the running processes. They are killed once the grace period is over.
+    fn cancel(&mut self) {
+        // On platforms other than Unix, the jobs terminate their processes themselves once the flag is set.
+        if !self.cancelled.swap(true, Ordering::Relaxed) {
+            #[cfg(unix)]
+            {
+                let policy = self.pool.termination_policy;
+
+                if !policy.grace_period.is_zero() {
+                    for (_, kill_handle) in self.threads.values() {
+                        let _ = kill_handle.signal(policy.signal);
+                    }
+                }
+
+                self.kill_deadline = Some(Instant::now() + policy.grace_period);
+            }
+        }
+    }
+
+    fn kill_running(&mut self) {
+        for (_, kill_handle) in self.threads.values() {
+            let _ = kill_handle.kill();
+        }
+
+        self.kill_deadline = None;
+    }
+}
+
+impl<I> Drop for JobResults<I> {
+    fn drop(&mut self) {
+        self.cancel();
+
+        #[cfg(unix)]
+        if let Some(deadline) = self.kill_deadline {
+            for (_, kill_handle) in self.threads.values() {
+                kill_handle.wait_exited(deadline.saturating_duration_since(Instant::now()));
+            }
+
+            self.kill_running();
+        }
+
+        for (_, (thread, _)) in self.threads.drain() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_job(
+    mut child: ChildGuard,
+    input: Option<Vec<u8>>,
+    cancelled: &AtomicBool,
+) -> Result<Output, io::Error> {
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    thread::scope(|scope| {
+        let stdin_handle = scope.spawn(move || match (stdin, input) {
+            (Some(mut stdin), Some(input)) => match stdin.write_all(&input) {
+                // The process has exited, or has been terminated, without reading all of the input.
+                Err(ref err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            },
+            _ => Ok(()),
+        });
+        let stdout_handle = scope.spawn(move || read_to_end(stdout));
+        let stderr_handle = scope.spawn(move || read_to_end(stderr));
+
+        let status = wait_job(&mut child, cancelled);
+
+        // The pipes are only closed once the process is gone, so it must not outlive this scope.
+        let status = status.inspect_err(|_| {
+            let _ = child.terminate();
+        })?;
+
+        let join_error = || io::Error::other("job thread panicked");
+
+        stdin_handle.join().map_err(|_| join_error())??;
+
+        Ok(Output {
+            status,
+            stdout: stdout_handle.join().map_err(|_| join_error())??,
+            stderr: stderr_handle.join().map_err(|_| join_error())??,
+        })
+    })
+}
+
+/// Wait for the process of a job. When the jobs are cancelled, the iterator terminates the process through its kill handle.
+#[cfg(unix)]
+#[inline]
+fn wait_job(child: &mut ChildGuard, _cancelled: &AtomicBool) -> Result<ExitStatus, io::Error> {
+    child.wait()
+}
+
+/// Wait for the process of a job. Processes cannot be signaled while another thread is waiting for them on platforms other than Unix, so the process is waited for in short steps, and terminated when the jobs are cancelled.
+#[cfg(not(unix))]
+fn wait_job(child: &mut ChildGuard, cancelled: &AtomicBool) -> Result<ExitStatus, io::Error> {
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            child.terminate()?;
+
+            return child.wait();
+        }
+
+        if let Some(status) = child.wait_timeout(POLL_INTERVAL)? {
+            return Ok(status);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::io;
+
+/// A Unix process group, which can be signaled as a whole, including the processes forked by its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessGroup {
+    id: u32,
+}
+
+impl ProcessGroup {
+    #[inline]
+    pub(crate) const fn new(id: u32) -> Self {
+        Self {
+            id,
+        }
+    }
+
+    /// Get the process group ID, which is the PID of the process group leader.
+    #[inline]
+    pub const fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Send a signal, such as `libc::SIGTERM`, to every process in this process group.
+    pub fn signal(&self, signal: i32) -> Result<(), io::Error> {
+        // SAFETY: `killpg` has no memory safety requirements.
+        if unsafe { libc::killpg(self.id as libc::pid_t, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Send `SIGKILL` to every process in this process group.
+    #[inline]
+    pub fn kill(&self) -> Result<(), io::Error> {
+        self.signal(libc::SIGKILL)
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    os::{
+        fd::{AsRawFd, FromRawFd},
+        unix::{fs::OpenOptionsExt, process::CommandExt},
+    },
+    process::{Command, Output, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::ChildGuard;
+
+/// The `VEOF` character, which ends the input of a terminal in canonical mode.
+pub(crate) const EOF_CHARACTER: u8 = 0x04;
+
+/// Options of the pseudo-terminal used by the `execute_pty*` methods.
+///
+/// The default window size is 24 rows and 80 columns, and the input is not echoed to the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PtyOptions {
+    rows:    u16,
+    columns: u16,
+    echo:    bool,
+}
+
+impl PtyOptions {
+    /// Create the default options.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            rows: 24, columns: 80, echo: false
+        }
+    }
+
+    /// Set the window size of the terminal.
+    #[inline]
+    pub const fn window_size(mut self, rows: u16, columns: u16) -> Self {
+        self.rows = rows;
+        self.columns = columns;
+
+        self
+    }
+
+    /// Echo the input data to the output, like an interactive terminal does when a user types.
+    #[inline]
+    pub const fn echo(mut self, enable: bool) -> Self {
+        self.echo = enable;
+
+        self
+    }
+}
+
+impl Default for PtyOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+fn check(result: libc::c_int) -> Result<libc::c_int, io::Error> {
+    if result == -1 { Err(io::Error::last_os_error()) } else { Ok(result) }
+}
+
+/// Open a pseudo-terminal. Returns the controller and the device file of the terminal.
+fn open_pty(options: PtyOptions) -> Result<(File, File), io::Error> {
+    // SAFETY: `posix_openpt` has no memory safety requirements, and the returned descriptor is owned by the `File`.
+    let controller = unsafe {
+        File::from_raw_fd(check(libc::posix_openpt(
+            libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+        ))?)
+    };
+
+    let fd = controller.as_raw_fd();
+    let mut name = [0 as libc::c_char; 128];
+
+    // SAFETY: `fd` is a valid pseudo-terminal controller, and `name` is valid for writes of its length.
+    unsafe {
+        check(libc::grantpt(fd))?;
+        check(libc::unlockpt(fd))?;
+
+        match libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) {
+            0 => (),
+            errno => return Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    // SAFETY: `ptsname_r` has written a nul-terminated string into `name`.
+    let name = unsafe { CStr::from_ptr(name.as_ptr()) };
+
+    let terminal = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(name.to_str().map_err(io::Error::other)?)?;
+
+    let window_size = libc::winsize {
+        ws_row:    options.rows,
+        ws_col:    options.columns,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    // SAFETY: `window_size` is a valid `winsize`.
+    check(unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &window_size) })?;
+
+    if !options.echo {
+        // SAFETY: `termios` is a plain C struct, for which all zero bytes is a valid value, and it is filled by `tcgetattr`.
+        unsafe {
+            let mut termios: libc::termios = mem::zeroed();
+
+            check(libc::tcgetattr(terminal.as_raw_fd(), &mut termios))?;
+
+            termios.c_lflag &= !libc::ECHO;
+
+            check(libc::tcsetattr(terminal.as_raw_fd(), libc::TCSANOW, &termios))?;
+        }
+    }
+
+    Ok((controller, terminal))
+}
+
+/// Spawn `command` with a new pseudo-terminal as its controlling terminal and its stdin, stdout and stderr. Returns the process and the controller of the terminal.
+pub(crate) fn spawn_pty(
+    command: &mut Command,
+    options: PtyOptions,
+) -> Result<(ChildGuard, File), io::Error> {
+    let (controller, terminal) = open_pty(options)?;
+
+    command.stdin(Stdio::from(terminal.try_clone()?));
+    command.stdout(Stdio::from(terminal.try_clone()?));
+    command.stderr(Stdio::from(terminal));
+
+    // `pre_exec` closures cannot be removed from the `Command`, so the closure is disabled after spawning, in case the command is executed again.
+    let active = Arc::new(AtomicBool::new(true));
+    let pre_exec_active = Arc::clone(&active);
+
+    // SAFETY: The closure only loads an atomic flag and calls `setsid` and `ioctl`, which are async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            if pre_exec_active.load(Ordering::Relaxed) {
+                // A new session can acquire the terminal, which makes it the controlling terminal of the process.
+                check(libc::setsid())?;
+                check(libc::ioctl(0, libc::TIOCSCTTY, 0))?;
+            }
+
+            Ok(())
+        });
+    }
+
+    let child = command.spawn();
+
+    active.store(false, Ordering::Relaxed);
+
+    // The `Command` keeps the terminal open until its stdio is replaced, and reading from the controller only ends after every terminal descriptor is closed.
+    command.stdin(Stdio::inherit());
+    command.stdout(Stdio::inherit());
+    command.stderr(Stdio::inherit());
+
+    Ok((ChildGuard::new(child?), controller))
+}
+
+/// Run `command` with a new pseudo-terminal. `data` is written as the input, followed by an end of input.
+pub(crate) fn execute_pty(
+    command: &mut Command,
+    options: PtyOptions,
+    data: &[u8],
+) -> Result<Output, io::Error> {
+    let (mut child, mut controller) = spawn_pty(command, options)?;
+
+    let output = communicate(&mut controller, data);
+    let status = child.wait()?;
+
+    Ok(Output {
+        status,
+        stdout: output?,
+        stderr: Vec::new(),
+    })
+}
+
+/// Write `data` and an end of input to the controller of a terminal while reading the output, until every descriptor of the terminal is closed.
+///
+/// Both are done in one thread with `poll`, because a write to the controller blocks forever if the process exits without reading all of the input.
+fn communicate(controller: &mut File, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let fd = controller.as_raw_fd();
+
+    // SAFETY: `fcntl` has no memory safety requirements.
+    unsafe {
+        let flags = check(libc::fcntl(fd, libc::F_GETFL))?;
+
+        check(libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK))?;
+    }
+
+    // In canonical mode, an end of input after a partial line only submits the line, so another one is needed.
+    let eof: &[u8] = if !data.is_empty() && !data.ends_with(b"\n") {
+        &[EOF_CHARACTER, EOF_CHARACTER]
+    } else {
+        &[EOF_CHARACTER]
+    };
+
+    let mut pending = [data, eof];
+    let mut pending_index = 0;
+
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        while pending_index < pending.len() && pending[pending_index].is_empty() {
+            pending_index += 1;
+        }
+
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: if pending_index < pending.len() {
+                libc::POLLIN | libc::POLLOUT
+            } else {
+                libc::POLLIN
+            },
+            revents: 0,
+        };
+
+        // SAFETY: `poll_fd` is a valid `pollfd`.
+        if unsafe { libc::poll(&mut poll_fd, 1, -1) } == -1 {
+            let err = io::Error::last_os_error();
+
+            if err.kind() == ErrorKind::Interrupted {
+                continue;
+            }
+
+            return Err(err);
+        }
+
+        if poll_fd.revents & libc::POLLOUT != 0 {
+            match controller.write(pending[pending_index]) {
+                Ok(c) => pending[pending_index] = &pending[pending_index][c..],
+                Err(ref err)
+                    if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {},
+                // The process has exited and closed the terminal without reading all of the input.
+                Err(ref err) if err.raw_os_error() == Some(libc::EIO) => {
+                    pending_index = pending.len()
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        if poll_fd.revents & (libc::POLLIN | libc::POLLHUP | libc::POLLERR) != 0 {
+            match controller.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(c) => output.extend_from_slice(&buffer[..c]),
+                Err(ref err)
+                    if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {},
+                // Linux reports `EIO` once every descriptor of the terminal is closed and all of the output is read.
+                Err(ref err) if err.raw_os_error() == Some(libc::EIO) => break,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    Ok(output)
+}
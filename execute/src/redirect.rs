@@ -0,0 +1,115 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// How to open a file for output redirection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum WriteMode {
+    /// Create the file or truncate it, like `>`.
+    #[default]
+    Truncate,
+    /// Create the file or append to it, like `>>`.
+    Append,
+}
+
+impl WriteMode {
+    fn open<P: AsRef<Path>>(self, path: P) -> Result<File, io::Error> {
+        let mut options = OpenOptions::new();
+
+        match self {
+            Self::Truncate => options.write(true).truncate(true),
+            Self::Append => options.append(true),
+        };
+
+        options.create(true).open(path)
+    }
+}
+
+/// Redirect the stdio of a command from or to files, like the `<`, `>`, `>>` and `2>&1` operators of shells.
+///
+/// For a pipeline, redirect the stdout and stderr of the last command and use `execute_multiple_output`, which does not override them.
+pub trait Redirect {
+    /// Read stdin from the file at `path`, like `< path`.
+    fn stdin_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, io::Error>;
+
+    /// Write stdout to the file at `path`, like `> path` or `>> path`.
+    fn stdout_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error>;
+
+    /// Write stderr to the file at `path`, like `2> path` or `2>> path`.
+    fn stderr_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error>;
+
+    /// Write both stdout and stderr to the file at `path` in the order they are written, like `> path 2>&1` or `>> path 2>&1`.
+    fn stdout_stderr_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error>;
+}
+
+impl Redirect for Command {
+    #[inline]
+    fn stdin_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, io::Error> {
+        Ok(self.stdin(File::open(path)?))
+    }
+
+    #[inline]
+    fn stdout_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error> {
+        Ok(self.stdout(mode.open(path)?))
+    }
+
+    #[inline]
+    fn stderr_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error> {
+        Ok(self.stderr(mode.open(path)?))
+    }
+
+    #[inline]
+    fn stdout_stderr_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WriteMode,
+    ) -> Result<&mut Self, io::Error> {
+        let file = mode.open(path)?;
+
+        // Both streams share one open file description, so they share the file offset instead of overwriting each other.
+        self.stdout(file.try_clone()?);
+
+        Ok(self.stderr(file))
+    }
+}
+
+/// Make both the stdout and stderr of `command` write to one new pipe, like `2>&1 |`. Returns the read end of the pipe.
+///
+/// [`reset_merged_output`] must be called after spawning, because the `Command` keeps the write end open, and reading the pipe would never end.
+pub(crate) fn merge_output(command: &mut Command) -> Result<File, io::Error> {
+    let (reader, writer) = crate::pipe::pipe()?;
+
+    command.stdout(writer.try_clone()?);
+    command.stderr(writer);
+
+    Ok(reader)
+}
+
+#[inline]
+pub(crate) fn reset_merged_output(command: &mut Command) {
+    command.stdout(Stdio::inherit());
+    command.stderr(Stdio::inherit());
+}
@@ -0,0 +1,148 @@
+use std::fmt::{self, Display, Formatter};
+#[cfg(unix)]
+use std::{io, os::unix::process::CommandExt, process::Command};
+
+/// Resource limits for a spawned process, set with `setrlimit` right before it executes the program.
+///
+/// Each limit is used as both the soft limit and the hard limit, so the process cannot raise it again. The only exception is the CPU time limit, whose hard limit is one second longer, so that the process receives `SIGXCPU` instead of `SIGKILL` when it runs out of CPU time.
+///
+/// An unprivileged process cannot raise its hard limits, so a limit larger than the current hard limit of the parent is lowered to it, rather than making the spawn fail with `EPERM`. A CPU time limit lowered this way has no extra second, so the process is killed with `SIGKILL` when it runs out of CPU time.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ResourceLimits {
+    cpu_time:      Option<u64>,
+    address_space: Option<u64>,
+    open_files:    Option<u64>,
+    file_size:     Option<u64>,
+    processes:     Option<u64>,
+}
+
+#[cfg(unix)]
+impl ResourceLimits {
+    /// Create limits which do not limit anything.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            cpu_time:      None,
+            address_space: None,
+            open_files:    None,
+            file_size:     None,
+            processes:     None,
+        }
+    }
+
+    /// Limit the CPU time in seconds (`RLIMIT_CPU`). The process receives `SIGXCPU` when it exceeds the limit.
+    #[inline]
+    pub const fn cpu_time(mut self, seconds: u64) -> Self {
+        self.cpu_time = Some(seconds);
+
+        self
+    }
+
+    /// Limit the size of the virtual memory in bytes (`RLIMIT_AS`). Allocations fail when the process exceeds the limit.
+    #[inline]
+    pub const fn address_space(mut self, bytes: u64) -> Self {
+        self.address_space = Some(bytes);
+
+        self
+    }
+
+    /// Limit the number of open file descriptors (`RLIMIT_NOFILE`).
+    #[inline]
+    pub const fn open_files(mut self, count: u64) -> Self {
+        self.open_files = Some(count);
+
+        self
+    }
+
+    /// Limit the size of the files written by the process in bytes (`RLIMIT_FSIZE`). The process receives `SIGXFSZ` when it exceeds the limit.
+    #[inline]
+    pub const fn file_size(mut self, bytes: u64) -> Self {
+        self.file_size = Some(bytes);
+
+        self
+    }
+
+    /// Limit the number of processes of the user of the process (`RLIMIT_NPROC`). Forking fails when the user exceeds the limit.
+    #[inline]
+    pub const fn processes(mut self, count: u64) -> Self {
+        self.processes = Some(count);
+
+        self
+    }
+
+    /// Make `command` set these limits for every process it spawns. It can be used with every `Execute` method, and with every stage of a pipeline.
+    pub fn apply<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        let limits = *self;
+
+        // SAFETY: The closure only calls `getrlimit` and `setrlimit`, which are async-signal-safe, and does not allocate.
+        unsafe { command.pre_exec(move || limits.set()) }
+    }
+
+    fn set(&self) -> Result<(), io::Error> {
+        let set_limit = |resource, soft: u64, hard: u64| {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0, rlim_max: 0
+            };
+
+            // SAFETY: `limit` is valid for writes.
+            if unsafe { libc::getrlimit(resource, &mut limit) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // Only a privileged process can raise its hard limit, so a larger limit is lowered to the current hard limit instead of failing.
+            let hard = (hard as libc::rlim_t).min(limit.rlim_max);
+
+            limit.rlim_cur = (soft as libc::rlim_t).min(hard);
+            limit.rlim_max = hard;
+
+            // SAFETY: `limit` is a valid `rlimit`.
+            if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        };
+
+        if let Some(seconds) = self.cpu_time {
+            set_limit(libc::RLIMIT_CPU, seconds, seconds.saturating_add(1))?;
+        }
+
+        if let Some(bytes) = self.address_space {
+            set_limit(libc::RLIMIT_AS, bytes, bytes)?;
+        }
+
+        if let Some(count) = self.open_files {
+            set_limit(libc::RLIMIT_NOFILE, count, count)?;
+        }
+
+        if let Some(bytes) = self.file_size {
+            set_limit(libc::RLIMIT_FSIZE, bytes, bytes)?;
+        }
+
+        if let Some(count) = self.processes {
+            set_limit(libc::RLIMIT_NPROC, count, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A resource limit which terminated a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitExceeded {
+    /// The process was terminated by `SIGXCPU`.
+    CpuTime,
+    /// The process was terminated by `SIGXFSZ`.
+    FileSize,
+}
+
+impl Display for LimitExceeded {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CpuTime => f.write_str("CPU limit exceeded"),
+            Self::FileSize => f.write_str("file size limit exceeded"),
+        }
+    }
+}
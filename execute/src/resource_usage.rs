@@ -0,0 +1,101 @@
+use std::time::Duration;
+#[cfg(target_os = "linux")]
+use std::{io, mem, os::unix::process::ExitStatusExt, process::ExitStatus};
+
+/// The resources used by a finished process, as reported by `wait4`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ResourceUsage {
+    user_time:                    Duration,
+    system_time:                  Duration,
+    max_rss:                      u64,
+    minor_page_faults:            u64,
+    major_page_faults:            u64,
+    voluntary_context_switches:   u64,
+    involuntary_context_switches: u64,
+}
+
+impl ResourceUsage {
+    /// Get the CPU time spent in user mode.
+    #[inline]
+    pub const fn user_time(&self) -> Duration {
+        self.user_time
+    }
+
+    /// Get the CPU time spent in kernel mode.
+    #[inline]
+    pub const fn system_time(&self) -> Duration {
+        self.system_time
+    }
+
+    /// Get the peak resident set size in bytes.
+    #[inline]
+    pub const fn max_rss(&self) -> u64 {
+        self.max_rss
+    }
+
+    /// Get the number of page faults serviced without any I/O.
+    #[inline]
+    pub const fn minor_page_faults(&self) -> u64 {
+        self.minor_page_faults
+    }
+
+    /// Get the number of page faults which required I/O.
+    #[inline]
+    pub const fn major_page_faults(&self) -> u64 {
+        self.major_page_faults
+    }
+
+    /// Get the number of times the process gave up the CPU voluntarily, usually to wait for I/O.
+    #[inline]
+    pub const fn voluntary_context_switches(&self) -> u64 {
+        self.voluntary_context_switches
+    }
+
+    /// Get the number of times the process was preempted.
+    #[inline]
+    pub const fn involuntary_context_switches(&self) -> u64 {
+        self.involuntary_context_switches
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<libc::rusage> for ResourceUsage {
+    fn from(rusage: libc::rusage) -> Self {
+        let duration =
+            |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+
+        Self {
+            user_time:                    duration(rusage.ru_utime),
+            system_time:                  duration(rusage.ru_stime),
+            // Linux reports the peak resident set size in kilobytes.
+            max_rss:                      rusage.ru_maxrss as u64 * 1024,
+            minor_page_faults:            rusage.ru_minflt as u64,
+            major_page_faults:            rusage.ru_majflt as u64,
+            voluntary_context_switches:   rusage.ru_nvcsw as u64,
+            involuntary_context_switches: rusage.ru_nivcsw as u64,
+        }
+    }
+}
+
+/// Wait for a child process to exit and reap it with `wait4`. Returns `None` if the process has already been reaped by `Child`.
+#[cfg(target_os = "linux")]
+pub(crate) fn wait4(pid: u32) -> Result<Option<(ExitStatus, ResourceUsage)>, io::Error> {
+    let mut status = 0;
+    // SAFETY: `rusage` is a plain C struct, for which all zero bytes is a valid value.
+    let mut rusage: libc::rusage = unsafe { mem::zeroed() };
+
+    loop {
+        // SAFETY: `status` and `rusage` are valid for writes.
+        if unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut rusage) } >= 0 {
+            return Ok(Some((ExitStatus::from_raw(status), rusage.into())));
+        }
+
+        let err = io::Error::last_os_error();
+
+        match err.raw_os_error() {
+            Some(libc::EINTR) => (),
+            Some(libc::ECHILD) => return Ok(None),
+            _ => return Err(err),
+        }
+    }
+}
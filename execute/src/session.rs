@@ -0,0 +1,276 @@
+#[cfg(target_os = "linux")]
+use std::fs::File;
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    process::{ChildStdin, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(target_os = "linux")]
+use crate::PtyOptions;
+use crate::{ChildGuard, Termination};
+
+/// An interactive session with a spawned process, for programs which read their input one prompt at a time, such as REPLs.
+///
+/// The output of the process is read by a background thread, so every read operation can time out while the process stays alive for further exchanges. When the session is dropped, its input is closed first, then the process is killed and reaped like a dropped [`ChildGuard`], and the thread is joined once the output ends. If the process has passed its output on to processes of its own which are still running, dropping the session waits for them to close it.
+#[derive(Debug)]
+pub struct Session {
+    input:  Option<Input>,
+    child:  ChildGuard,
+    output: Receiver<Result<Vec<u8>, io::Error>>,
+    reader: Option<JoinHandle<()>>,
+    buffer: Vec<u8>,
+    /// Whether the output has ended.
+    eof:    bool,
+}
+
+#[derive(Debug)]
+enum Input {
+    Pipe(ChildStdin),
+    /// The controller of a pseudo-terminal, whose input is ended with a character instead of by closing it.
+    #[cfg(target_os = "linux")]
+    Terminal(File),
+}
+
+impl Write for Input {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        match self {
+            Self::Pipe(stdin) => stdin.write(buf),
+            #[cfg(target_os = "linux")]
+            Self::Terminal(controller) => controller.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<(), io::Error> {
+        match self {
+            Self::Pipe(stdin) => stdin.flush(),
+            #[cfg(target_os = "linux")]
+            Self::Terminal(controller) => controller.flush(),
+        }
+    }
+}
+
+impl Session {
+    /// Spawn `command` with piped stdin and stdout. By default, stderr is inherited from the parent.
+    pub fn spawn(command: &mut Command) -> Result<Self, io::Error> {
+        command.stdin(Stdio::piped());
+        command.stdout(Stdio::piped());
+
+        let mut child = ChildGuard::new(command.spawn()?);
+
+        let input = crate::take_child_stdin(&mut child)?;
+        let output = crate::take_child_stdout(&mut child)?;
+
+        Ok(Self::new(child, Input::Pipe(input), output))
+    }
+
+    /// Spawn `command` attached to a new pseudo-terminal, so it behaves as if it were run interactively. The output of both stdout and stderr is read from the terminal, with newlines translated into `\r\n`.
+    ///
+    /// Like [`Execute::execute_pty_output`](crate::Execute::execute_pty_output), this sets the stdin, stdout and stderr of `command` to the terminal and resets them to being inherited from the parent after spawning, and leaves a disabled `pre_exec` closure on `command`.
+    #[cfg(target_os = "linux")]
+    pub fn spawn_pty(command: &mut Command, options: PtyOptions) -> Result<Self, io::Error> {
+        let (child, controller) = crate::pty::spawn_pty(command, options)?;
+
+        let output = controller.try_clone()?;
+
+        Ok(Self::new(child, Input::Terminal(controller), output))
+    }
+
+    fn new<R: Read + Send + 'static>(child: ChildGuard, input: Input, mut output: R) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        // The thread ends when the output ends or the session is dropped.
+        let reader = thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+
+            loop {
+                let chunk = match output.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(c) => Ok(buffer[..c].to_vec()),
+                    Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+                    // Linux reports `EIO` once every descriptor of a pseudo-terminal is closed.
+                    #[cfg(target_os = "linux")]
+                    Err(ref err) if err.raw_os_error() == Some(libc::EIO) => break,
+                    Err(err) => Err(err),
+                };
+
+                let failed = chunk.is_err();
+
+                if sender.send(chunk).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            child,
+            input: Some(input),
+            output: receiver,
+            reader: Some(reader),
+            buffer: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Get the process of this session.
+    #[inline]
+    pub fn child(&self) -> &ChildGuard {
+        &self.child
+    }
+
+    /// Get the process of this session.
+    #[inline]
+    pub fn child_mut(&mut self) -> &mut ChildGuard {
+        &mut self.child
+    }
+
+    /// Write data to the input of the process.
+    pub fn send<D: ?Sized + AsRef<[u8]>>(&mut self, data: &D) -> Result<(), io::Error> {
+        let input = self.input.as_mut().ok_or_else(|| {
+            io::Error::new(ErrorKind::BrokenPipe, "the input of the session has been closed")
+        })?;
+
+        input.write_all(data.as_ref())?;
+        input.flush()
+    }
+
+    /// Write a line to the input of the process. A newline is appended to `line`.
+    #[inline]
+    pub fn send_line<S: ?Sized + AsRef<str>>(&mut self, line: &S) -> Result<(), io::Error> {
+        self.send(format!("{}\n", line.as_ref()).as_bytes())
+    }
+
+    /// End the input of the process. For a pseudo-terminal, an end-of-input character is sent, which the terminal only treats as such at the start of a line.
+    pub fn send_eof(&mut self) -> Result<(), io::Error> {
+        match self.input {
+            #[cfg(target_os = "linux")]
+            Some(Input::Terminal(_)) => self.send(&[crate::pty::EOF_CHARACTER]),
+            _ => {
+                self.input = None;
+
+                Ok(())
+            },
+        }
+    }
+
+    /// Read the output until `pattern` appears, and return the output up to and including it. Returns an error of kind `TimedOut` if it does not appear within `timeout`, or `UnexpectedEof` if the output ends. The output is kept for the next read on error.
+    pub fn expect<P: ?Sized + AsRef<[u8]>>(
+        &mut self,
+        pattern: &P,
+        timeout: Duration,
+    ) -> Result<Vec<u8>, io::Error> {
+        let pattern = pattern.as_ref();
+
+        self.read_while(timeout, |buffer, searched| {
+            if pattern.is_empty() {
+                return Some(0);
+            }
+
+            // A match may start in the part which has been searched and end in the new output.
+            let start = searched.saturating_sub(pattern.len() - 1);
+
+            buffer[start..]
+                .windows(pattern.len())
+                .position(|window| window == pattern)
+                .map(|i| start + i + pattern.len())
+        })
+    }
+
+    /// Read the output until the `delimiter` byte, and return the output up to and including it. See [`expect`](Self::expect).
+    #[inline]
+    pub fn read_until(&mut self, delimiter: u8, timeout: Duration) -> Result<Vec<u8>, io::Error> {
+        self.read_while(timeout, |buffer, searched| {
+            buffer[searched..].iter().position(|b| *b == delimiter).map(|i| searched + i + 1)
+        })
+    }
+
+    /// Read the output until it ends, which usually happens when the process exits.
+    pub fn read_to_end(&mut self, timeout: Duration) -> Result<Vec<u8>, io::Error> {
+        let deadline = Instant::now() + timeout;
+
+        while !self.eof {
+            self.receive(deadline)?;
+        }
+
+        Ok(mem::take(&mut self.buffer))
+    }
+
+    /// Wait for the process to exit, after ending its input.
+    pub fn wait(&mut self) -> Result<ExitStatus, io::Error> {
+        self.send_eof()?;
+
+        self.child.wait()
+    }
+
+    /// Terminate the process according to its termination policy. See [`ChildGuard::terminate`].
+    #[inline]
+    pub fn terminate(&mut self) -> Result<Termination, io::Error> {
+        self.child.terminate()
+    }
+
+    /// Read until `find_end` finds the end of the data to return in the buffer. It is also given the length of the part of the buffer it has already searched without finding the end, so that each chunk of the output is only searched once.
+    fn read_while<F: Fn(&[u8], usize) -> Option<usize>>(
+        &mut self,
+        timeout: Duration,
+        find_end: F,
+    ) -> Result<Vec<u8>, io::Error> {
+        let deadline = Instant::now() + timeout;
+
+        let mut searched = 0;
+
+        loop {
+            if let Some(end) = find_end(&self.buffer, searched) {
+                let rest = self.buffer.split_off(end);
+
+                return Ok(mem::replace(&mut self.buffer, rest));
+            }
+
+            searched = self.buffer.len();
+
+            if self.eof {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "the output of the session ended",
+                ));
+            }
+
+            self.receive(deadline)?;
+        }
+    }
+
+    /// Append the next chunk of the output to the buffer.
+    fn receive(&mut self, deadline: Instant) -> Result<(), io::Error> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        match self.output.recv_timeout(timeout) {
+            Ok(chunk) => self.buffer.extend_from_slice(&chunk?),
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "timed out waiting for the output of the session",
+                ));
+            },
+            Err(RecvTimeoutError::Disconnected) => self.eof = true,
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // The input is closed before the process is killed, and the output ends once the process is gone.
+        self.input = None;
+
+        let _ = self.child.kill();
+
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    fs::File,
+    io::{self, ErrorKind, Read, Write},
+    process::{ChildStdout, Command, Stdio},
+    thread::{self, ScopedJoinHandle},
+};
+
+use crate::{ChildGuard, Pipeline, take_child_stdout};
+
+/// The function of an in-process stage, which reads the output of the previous stage and writes the input of the next stage.
+pub type StageFunction<'a> =
+    Box<dyn FnOnce(&mut dyn Read, &mut dyn Write) -> Result<(), io::Error> + Send + 'a>;
+
+/// A stage of a pipeline executed by the `execute_multiple_stages*` methods, either an external command or a Rust function run on its own thread.
+pub enum Stage<'a> {
+    /// An external command.
+    Command(&'a mut Command),
+    /// An in-process function.
+    Function(StageFunction<'a>),
+}
+
+impl<'a> Stage<'a> {
+    /// Create an in-process stage from a function.
+    ///
+    /// The output of the previous stage is ended when the function returns. If the next stage exits before reading all of the output, writing fails with an error of kind `BrokenPipe`, which is ignored like `SIGPIPE` ending an external command.
+    #[inline]
+    pub fn function<F>(function: F) -> Self
+    where
+        F: FnOnce(&mut dyn Read, &mut dyn Write) -> Result<(), io::Error> + Send + 'a, {
+        Self::Function(Box::new(function))
+    }
+}
+
+impl<'a> From<&'a mut Command> for Stage<'a> {
+    #[inline]
+    fn from(command: &'a mut Command) -> Self {
+        Self::Command(command)
+    }
+}
+
+impl Debug for Stage<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command(command) => f.debug_tuple("Command").field(command).finish(),
+            Self::Function(_) => f.write_str("Function"),
+        }
+    }
+}
+
+/// The output of the previous stage.
+enum StageOutput {
+    Child(ChildStdout),
+    Function(File),
+}
+
+impl From<StageOutput> for Stdio {
+    #[inline]
+    fn from(output: StageOutput) -> Self {
+        match output {
+            StageOutput::Child(stdout) => stdout.into(),
+            StageOutput::Function(reader) => reader.into(),
+        }
+    }
+}
+
+impl Read for StageOutput {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        match self {
+            Self::Child(stdout) => stdout.read(buf),
+            Self::Function(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Spawn `first` and `stages`, connected with pipes, then wait for the processes with `wait` and for the function stages to finish.
+///
+/// The stdout and stderr of `first` and of the last stage must have been set up by the caller. The last stage must be a command, and `stages` must not be empty.
+pub(crate) fn execute_stages<T, W>(
+    first: &mut Command,
+    stages: Vec<Stage<'_>>,
+    wait: W,
+) -> Result<T, io::Error>
+where
+    W: FnOnce(Pipeline) -> Result<T, io::Error>, {
+    if matches!(stages.last(), Some(Stage::Function(_))) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "the last stage of a pipeline must be a command",
+        ));
+    }
+
+    thread::scope(|scope| {
+        let mut children = Vec::with_capacity(stages.len() + 1);
+        let mut handles = Vec::new();
+
+        // Returning early drops the spawned children, which terminates them, and the ends of their pipes, which ends the function stages.
+        children.push(ChildGuard::new(first.spawn()?));
+
+        let mut output = StageOutput::Child(take_child_stdout(&mut children[0])?);
+
+        let last_index = stages.len() - 1;
+
+        for (index, stage) in stages.into_iter().enumerate() {
+            match stage {
+                Stage::Command(command) => {
+                    command.stdin(output);
+
+                    if index < last_index {
+                        command.stdout(Stdio::piped());
+                        command.stderr(Stdio::null());
+                    }
+
+                    let child = command.spawn();
+
+                    // The `Command` keeps the read end of the pipe open until its stdin is replaced.
+                    command.stdin(Stdio::inherit());
+
+                    children.push(ChildGuard::new(child?));
+
+                    if index == last_index {
+                        break;
+                    }
+
+                    output = StageOutput::Child(take_child_stdout(children.last_mut().unwrap())?);
+                },
+                Stage::Function(function) => {
+                    let (reader, mut writer) = crate::pipe::pipe()?;
+                    let mut input = output;
+
+                    handles.push(scope.spawn(move || {
+                        match function(&mut input, &mut writer).and_then(|_| writer.flush()) {
+                            Err(ref err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+                            result => result,
+                        }
+                    }));
+
+                    output = StageOutput::Function(reader);
+                },
+            }
+        }
+
+        let result = wait(Pipeline::from_children(children));
+
+        let function_result = join_functions(handles);
+
+        match result {
+            Ok(value) => {
+                function_result?;
+
+                Ok(value)
+            },
+            Err(err) => Err(err),
+        }
+    })
+}
+
+fn join_functions(
+    handles: Vec<ScopedJoinHandle<'_, Result<(), io::Error>>>,
+) -> Result<(), io::Error> {
+    let mut first_error = None;
+
+    for handle in handles {
+        let result = match handle.join() {
+            Ok(result) => result,
+            Err(_) => Err(io::Error::other("pipeline stage thread panicked")),
+        };
+
+        if let Err(err) = result {
+            if first_error.is_none() {
+                first_error = Some(err);
+            }
+        }
+    }
+
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
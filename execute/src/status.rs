@@ -0,0 +1,218 @@
+#[cfg(unix)]
+use std::os::unix::process::ExitStatusExt;
+use std::{
+    fmt::{self, Display, Formatter},
+    process::ExitStatus,
+};
+
+use crate::{LimitExceeded, ResourceUsage};
+
+/// The status of a finished process, which tells how it ended when there is no exit status code.
+///
+/// On Unix, a process without an exit status code was terminated by a signal, such as `SIGKILL` from the OOM killer or `SIGSEGV` from a crash. On other platforms, every process has an exit status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessStatus {
+    status:         ExitStatus,
+    resource_usage: Option<ResourceUsage>,
+}
+
+impl ProcessStatus {
+    /// Get the underlying `ExitStatus`.
+    #[inline]
+    pub const fn exit_status(&self) -> ExitStatus {
+        self.status
+    }
+
+    /// Get the resources used by the process. Only the statuses collected with `wait4` on Linux, such as the ones returned by `execute_with_resource_usage`, have it.
+    #[inline]
+    pub const fn resource_usage(&self) -> Option<ResourceUsage> {
+        self.resource_usage
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub(crate) const fn with_resource_usage(mut self, resource_usage: ResourceUsage) -> Self {
+        self.resource_usage = Some(resource_usage);
+
+        self
+    }
+
+    /// Check whether the process exited with the status code `0`.
+    #[inline]
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// Get the exit status code, or `None` if the process was terminated by a signal.
+    #[inline]
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+
+    /// Get the number of the signal which terminated the process.
+    #[inline]
+    pub fn signal(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            self.status.signal()
+        }
+
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Get the name of the signal which terminated the process, such as `"SIGSEGV"`.
+    #[inline]
+    pub fn signal_name(&self) -> Option<&'static str> {
+        self.signal().and_then(signal_name)
+    }
+
+    /// Get the resource limit which terminated the process, such as the CPU time limit set by [`ResourceLimits`](crate::ResourceLimits).
+    #[inline]
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        #[cfg(unix)]
+        {
+            match self.signal()? {
+                libc::SIGXCPU => Some(LimitExceeded::CpuTime),
+                libc::SIGXFSZ => Some(LimitExceeded::FileSize),
+                _ => None,
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Check whether the process dumped a core when it was terminated by a signal.
+    #[inline]
+    pub fn core_dumped(&self) -> bool {
+        #[cfg(unix)]
+        {
+            self.status.core_dumped()
+        }
+
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+
+    /// Get the number of the signal which stopped the process. Only a status collected with `WUNTRACED` can be a stopped one, so this is always `None` for the statuses returned by this crate.
+    #[inline]
+    pub fn stopped_signal(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            self.status.stopped_signal()
+        }
+
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Check whether the process was stopped.
+    #[inline]
+    pub fn stopped(&self) -> bool {
+        self.stopped_signal().is_some()
+    }
+}
+
+impl From<ExitStatus> for ProcessStatus {
+    #[inline]
+    fn from(status: ExitStatus) -> Self {
+        Self {
+            status,
+            resource_usage: None,
+        }
+    }
+}
+
+impl From<ProcessStatus> for ExitStatus {
+    #[inline]
+    fn from(status: ProcessStatus) -> Self {
+        status.status
+    }
+}
+
+impl Display for ProcessStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(code) = self.code() {
+            return write!(f, "exit status: {code}");
+        }
+
+        let (kind, signal) = match (self.signal(), self.stopped_signal()) {
+            (Some(signal), _) => ("signal", signal),
+            (None, Some(signal)) => ("stopped", signal),
+            (None, None) => return Display::fmt(&self.status, f),
+        };
+
+        write!(f, "{kind}: {signal}")?;
+
+        if let Some(name) = signal_name(signal) {
+            write!(f, " ({name})")?;
+        }
+
+        if let Some(limit_exceeded) = self.limit_exceeded() {
+            write!(f, " ({limit_exceeded})")?;
+        }
+
+        if self.core_dumped() {
+            f.write_str(" (core dumped)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the name of a signal number, such as `"SIGKILL"` for `9`. Returns `None` for unknown signals and on platforms other than Unix.
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    #[cfg(unix)]
+    {
+        let name = match signal {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGCHLD => "SIGCHLD",
+            libc::SIGCONT => "SIGCONT",
+            libc::SIGSTOP => "SIGSTOP",
+            libc::SIGTSTP => "SIGTSTP",
+            libc::SIGTTIN => "SIGTTIN",
+            libc::SIGTTOU => "SIGTTOU",
+            libc::SIGURG => "SIGURG",
+            libc::SIGXCPU => "SIGXCPU",
+            libc::SIGXFSZ => "SIGXFSZ",
+            libc::SIGVTALRM => "SIGVTALRM",
+            libc::SIGPROF => "SIGPROF",
+            libc::SIGWINCH => "SIGWINCH",
+            libc::SIGIO => "SIGIO",
+            libc::SIGSYS => "SIGSYS",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal;
+
+        None
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    process::{ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio},
+    thread,
+};
+
+use crate::{ChildGuard, Pipeline, take_child_stdin, take_child_stdout};
+
+/// The output of a command whose stdout was copied into several branch commands, like `tee >(command1) >(command2)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeeOutput {
+    /// The exit status of the last process of the producer.
+    pub status:   ExitStatus,
+    /// The output of every branch, in the order the branches were given.
+    pub branches: Vec<Output>,
+}
+
+/// Copy the stdout of the last process of `producer` into the stdin of every branch, then wait for all of the processes.
+pub(crate) fn execute_tee(
+    mut producer: Pipeline,
+    branches: &mut [&mut Command],
+) -> Result<TeeOutput, io::Error> {
+    let input = take_child_stdout(producer.last_child_mut())?;
+
+    let mut children = Vec::with_capacity(branches.len());
+    let mut outputs = Vec::with_capacity(branches.len());
+
+    // Returning early drops the producer and the spawned branches, which terminates them.
+    for branch in branches.iter_mut() {
+        branch.stdin(Stdio::piped());
+
+        let mut child = ChildGuard::new(branch.spawn()?);
+
+        outputs.push(Some(take_child_stdin(&mut child)?));
+        children.push(child);
+    }
+
+    let (copy_result, branch_results) = thread::scope(|scope| {
+        // Every branch is drained on its own thread, so a branch blocked on its stdout cannot stop the others from reading their input.
+        let handles: Vec<_> = children
+            .iter_mut()
+            .map(|child| scope.spawn(move || child.wait_with_output()))
+            .collect();
+
+        let copy_result = copy_to_branches(input, outputs);
+
+        let branch_results: Vec<Result<Output, io::Error>> = handles
+            .into_iter()
+            .map(|handle| match handle.join() {
+                Ok(result) => result,
+                Err(_) => Err(io::Error::other("branch wait thread panicked")),
+            })
+            .collect();
+
+        (copy_result, branch_results)
+    });
+
+    let status = producer.wait_status()?;
+
+    copy_result?;
+
+    Ok(TeeOutput {
+        status:   status.exit_status(),
+        branches: branch_results.into_iter().collect::<Result<Vec<Output>, io::Error>>()?,
+    })
+}
+
+/// Write every chunk of `input` to every output before reading the next one, so the slowest branch limits the speed and the memory used stays bounded.
+fn copy_to_branches(
+    mut input: ChildStdout,
+    mut outputs: Vec<Option<ChildStdin>>,
+) -> Result<(), io::Error> {
+    let mut buffer = [0u8; 8192];
+
+    while outputs.iter().any(Option::is_some) {
+        let c = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(c) => c,
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+
+        for output in outputs.iter_mut() {
+            if let Some(stdin) = output {
+                match stdin.write_all(&buffer[..c]) {
+                    Ok(()) => (),
+                    // The branch has exited without reading all of the input, which does not affect the other branches.
+                    Err(ref err) if err.kind() == ErrorKind::BrokenPipe => *output = None,
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{ChildGuard, pipeline::ProcessGroupHandle};
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[cfg(unix)]
+type Signal = i32;
+
+#[cfg(unix)]
+const DEFAULT_SIGNAL: Signal = libc::SIGTERM;
+
+/// Signals are only supported on Unix.
+#[cfg(not(unix))]
+type Signal = ();
+
+#[cfg(not(unix))]
+const DEFAULT_SIGNAL: Signal = ();
+
+/// How the `terminate` methods end processes, such as ones which are cancelled or timed out, and how the `execute_multiple*` methods end the other processes of a pipeline when one of its stages fails. Processes which are dropped without being waited for are always killed right away instead.
+///
+/// A termination signal is sent first, so that programs which flush their output or clean up on `SIGTERM` have a chance to exit by themselves. The processes which are still running after the grace period are killed with `SIGKILL`. On platforms other than Unix, processes are killed immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TerminationPolicy {
+    pub(crate) signal:       Signal,
+    pub(crate) grace_period: Duration,
+}
+
+impl TerminationPolicy {
+    /// The default grace period, which is 5 seconds.
+    pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+    /// Create the default policy, which sends `SIGTERM` and waits for 5 seconds before sending `SIGKILL`.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            signal: DEFAULT_SIGNAL, grace_period: Self::DEFAULT_GRACE_PERIOD
+        }
+    }
+
+    /// Create a policy which kills processes immediately, without a grace period.
+    #[inline]
+    pub const fn kill() -> Self {
+        Self::new().grace_period(Duration::ZERO)
+    }
+
+    /// Set the signal which is sent first, such as `libc::SIGINT`.
+    #[cfg(unix)]
+    #[inline]
+    pub const fn signal(mut self, signal: i32) -> Self {
+        self.signal = signal;
+
+        self
+    }
+
+    /// Set how long to wait after the first signal before sending `SIGKILL`. A zero grace period skips the first signal.
+    #[inline]
+    pub const fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+
+        self
+    }
+}
+
+impl Default for TerminationPolicy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The step of a [`TerminationPolicy`] which actually ended the processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// Every process had already exited, so no signal was sent.
+    AlreadyExited,
+    /// Every process exited within the grace period after the termination signal.
+    Signal,
+    /// Some processes were still running after the grace period and were killed with `SIGKILL`.
+    Kill,
+}
+
+/// Terminate `children` and `process_group` according to `policy`, and reap the children.
+///
+/// Every child must be a member of `process_group`. The group is only signaled while at least one child has not been reaped, because the group ID could have been reused after that.
+pub(crate) fn terminate_children(
+    children: &mut [&mut ChildGuard],
+    process_group: Option<ProcessGroupHandle>,
+    policy: TerminationPolicy,
+) -> Result<Termination, io::Error> {
+    let mut running = Vec::with_capacity(children.len());
+
+    for child in children.iter_mut() {
+        if child.try_wait()?.is_none() {
+            running.push(&mut **child);
+        }
+    }
+
+    if running.is_empty() {
+        return Ok(Termination::AlreadyExited);
+    }
+
+    if !policy.grace_period.is_zero() && send_signal(&mut running, process_group, policy)? {
+        let deadline = Instant::now() + policy.grace_period;
+
+        loop {
+            let mut still_running = Vec::with_capacity(running.len());
+
+            for child in running {
+                if child.try_wait()?.is_none() {
+                    still_running.push(child);
+                }
+            }
+
+            running = still_running;
+
+            if running.is_empty() {
+                return Ok(Termination::Signal);
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                break;
+            }
+
+            thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    kill_process_group(process_group)?;
+
+    for child in &mut running {
+        child.kill()?;
+    }
+
+    for child in running {
+        child.wait()?;
+    }
+
+    Ok(Termination::Kill)
+}
+
+/// Send the termination signal of `policy`. Returns `false` if there is nothing left to signal.
+#[cfg(unix)]
+fn send_signal(
+    running: &mut [&mut ChildGuard],
+    process_group: Option<ProcessGroupHandle>,
+    policy: TerminationPolicy,
+) -> Result<bool, io::Error> {
+    if let Some(process_group) = process_group {
+        match process_group.signal(policy.signal) {
+            Ok(()) => return Ok(true),
+            // Every process of the group has been reaped, so only the children themselves are left to check.
+            Err(err) if err.raw_os_error() == Some(libc::ESRCH) => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    for child in running.iter() {
+        // SAFETY: `kill` has no memory safety requirements. The child has not been reaped, so its PID cannot have been reused.
+        if unsafe { libc::kill(child.id() as libc::pid_t, policy.signal) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(!running.is_empty())
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn send_signal(
+    _running: &mut [&mut ChildGuard],
+    _process_group: Option<ProcessGroupHandle>,
+    _policy: TerminationPolicy,
+) -> Result<bool, io::Error> {
+    // There is no termination signal, so the processes are killed right away.
+    Ok(false)
+}
+
+#[cfg(unix)]
+fn kill_process_group(process_group: Option<ProcessGroupHandle>) -> Result<(), io::Error> {
+    match process_group.map(|process_group| process_group.kill()) {
+        Some(Err(err)) if err.raw_os_error() != Some(libc::ESRCH) => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+#[inline]
+fn kill_process_group(process_group: Option<ProcessGroupHandle>) -> Result<(), io::Error> {
+    match process_group {
+        Some(process_group) => match process_group {},
+        None => Ok(()),
+    }
+}
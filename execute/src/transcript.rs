@@ -0,0 +1,148 @@
+use std::{
+    io::{self, ErrorKind, Read},
+    process::{ExitStatus, Output},
+    sync::mpsc::{self, Sender},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::ChildGuard;
+
+/// An output stream of a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+}
+
+/// A chunk of output read from a process.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TranscriptChunk {
+    /// The stream which the chunk was read from.
+    pub stream:  Stream,
+    /// The time elapsed since the process was spawned when the chunk was read.
+    pub elapsed: Duration,
+    /// The bytes which were read.
+    pub data:    Vec<u8>,
+}
+
+/// The stdout and stderr of a finished process, recorded as chunks in the order they arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    /// The exit status of the process.
+    pub status:     ExitStatus,
+    /// The time when the process was spawned.
+    pub started_at: SystemTime,
+    /// The chunks of stdout and stderr, sorted by the time they were read.
+    pub chunks:     Vec<TranscriptChunk>,
+}
+
+impl Transcript {
+    /// Concatenate the chunks of stdout.
+    #[inline]
+    pub fn stdout(&self) -> Vec<u8> {
+        self.concat(Stream::Stdout)
+    }
+
+    /// Concatenate the chunks of stderr.
+    #[inline]
+    pub fn stderr(&self) -> Vec<u8> {
+        self.concat(Stream::Stderr)
+    }
+
+    fn concat(&self, stream: Stream) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.stream == stream)
+            .flat_map(|chunk| &chunk.data)
+            .copied()
+            .collect()
+    }
+}
+
+impl From<Transcript> for Output {
+    #[inline]
+    fn from(transcript: Transcript) -> Self {
+        Output {
+            status: transcript.status,
+            stdout: transcript.stdout(),
+            stderr: transcript.stderr(),
+        }
+    }
+}
+
+/// Read the piped stdout and stderr of `child` until both end, then wait for it.
+pub(crate) fn record(
+    child: &mut ChildGuard,
+    started: Instant,
+    started_at: SystemTime,
+) -> Result<Transcript, io::Error> {
+    drop(child.stdin.take());
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (sender, receiver) = mpsc::channel();
+
+    let results = thread::scope(|scope| {
+        let stdout_sender = sender.clone();
+
+        let stdout_handle =
+            scope.spawn(move || read_chunks(stdout, Stream::Stdout, started, stdout_sender));
+        let stderr_handle =
+            scope.spawn(move || read_chunks(stderr, Stream::Stderr, started, sender));
+
+        [stdout_handle.join(), stderr_handle.join()]
+    });
+
+    for result in results {
+        match result {
+            Ok(result) => result?,
+            Err(_) => return Err(io::Error::other("child output reading thread panicked")),
+        }
+    }
+
+    let mut chunks: Vec<TranscriptChunk> = receiver.into_iter().collect();
+
+    // The chunks are sent in nearly the order they were read, and sorting by the time makes the order exact.
+    chunks.sort_by_key(|chunk| chunk.elapsed);
+
+    Ok(Transcript {
+        status: child.wait()?,
+        started_at,
+        chunks,
+    })
+}
+
+fn read_chunks<R: Read>(
+    reader: Option<R>,
+    stream: Stream,
+    started: Instant,
+    sender: Sender<TranscriptChunk>,
+) -> Result<(), io::Error> {
+    let Some(mut reader) = reader else {
+        return Ok(());
+    };
+
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(c) => {
+                let chunk = TranscriptChunk {
+                    stream,
+                    elapsed: started.elapsed(),
+                    data: buffer[..c].to_vec(),
+                };
+
+                // The receiver lives until both readers end.
+                let _ = sender.send(chunk);
+            },
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+#![cfg(target_os = "linux")]
+
+use std::{
+    env, fs, mem,
+    os::unix::process::CommandExt,
+    process::{self, Command},
+};
+
+use execute::{BatchOptions, Execute};
+
+/// The size an argument of `length` bytes takes in a batch.
+fn arg_size(length: usize) -> usize {
+    length + 1 + mem::size_of::<usize>()
+}
+
+#[test]
+fn execute_batches_arg_limit() {
+    let path = env::temp_dir().join(format!("execute-batches-{}", process::id()));
+
+    // Every batch appends its arguments as one line.
+    let mut command = execute::shell(format!("echo \"$@\" >> {}", path.display()));
+
+    command.arg("sh");
+
+    let args: Vec<String> = (0..10).map(|i| format!("arg{i}")).collect();
+
+    let reports =
+        command.execute_batches(&args, BatchOptions::new().arg_limit(arg_size(4) * 3)).unwrap();
+
+    assert_eq!(
+        vec![0..3, 3..6, 6..9, 9..10],
+        reports.iter().map(|report| report.arguments.clone()).collect::<Vec<_>>()
+    );
+    assert!(reports.iter().all(|report| report.success()));
+    assert_eq!(
+        "arg0 arg1 arg2\narg3 arg4 arg5\narg6 arg7 arg8\narg9\n",
+        fs::read_to_string(&path).unwrap()
+    );
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn execute_batches_max_args() {
+    let mut command = execute::shell("test $# -le 4");
+
+    command.arg("sh");
+
+    let reports = command
+        .execute_batches(
+            (0..100).map(|i| i.to_string()),
+            BatchOptions::new().max_args(4).concurrency(4),
+        )
+        .unwrap();
+
+    assert_eq!(25, reports.len());
+    assert!(reports.iter().all(|report| report.success()));
+    assert_eq!(96..100, reports[24].arguments);
+}
+
+#[test]
+fn execute_batches_default_limit() {
+    // Together, the arguments are much longer than `ARG_MAX` on common systems.
+    let args: Vec<String> = (0..20000).map(|i| format!("{i:0>100}")).collect();
+
+    let mut command = execute::shell("test $# -gt 0");
+
+    command.arg("sh");
+
+    let reports = command.execute_batches(&args, BatchOptions::new()).unwrap();
+
+    assert!(reports.len() > 1);
+    assert!(reports.iter().all(|report| report.success()));
+    assert_eq!(20000, reports.last().unwrap().arguments.end);
+}
+
+#[test]
+fn execute_batches_argument_too_long() {
+    let mut command = execute::shell("exit 0");
+
+    let err = command
+        .execute_batches(["a".repeat(100)], BatchOptions::new().arg_limit(arg_size(10)))
+        .unwrap_err();
+
+    assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn execute_batches_single_argument_too_long() {
+    let mut command = execute::shell("exit 0");
+
+    // Linux limits each argument to 32 pages, however large `ARG_MAX` is.
+    let err = command
+        .execute_batches(["a".repeat(1 << 20)], BatchOptions::new().arg_limit(1 << 30))
+        .unwrap_err();
+
+    assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn execute_batches_factory() {
+    let reports = execute::execute_batches(
+        || {
+            // Each batch checks that it leads its own process group.
+            let mut command = Command::new("sh");
+
+            command.arg("-c").arg(r#"set -- $(cat /proc/$$/stat); test "$5" = $$"#).arg("sh");
+
+            command.process_group(0);
+
+            command
+        },
+        (0..100).map(|i| i.to_string()),
+        BatchOptions::new().max_args(10),
+    )
+    .unwrap();
+
+    assert_eq!(10, reports.len());
+    assert!(reports.iter().all(|report| report.success()));
+}
@@ -0,0 +1,54 @@
+#![cfg(windows)]
+
+use std::{
+    env,
+    process::{Command, Stdio},
+};
+
+use execute::BatchOptions;
+
+/// Create a command which takes any arguments and succeeds. The arguments are test name filters of this test binary, which only lists the tests matching them exactly, so none are run.
+fn list_tests() -> Command {
+    let mut command = Command::new(env::current_exe().unwrap());
+
+    command.arg("--list").arg("--exact");
+    command.stdout(Stdio::null());
+
+    command
+}
+
+#[test]
+fn execute_batches_arg_limit_utf16() {
+    // Each argument takes its quoted length in UTF-16 units and a space, so these take 6, 5, 2, 2 and 8 units.
+    let args = ["a b", "c\"d", "é", "é", "x y\\"];
+
+    let reports =
+        execute::execute_batches(list_tests, args, BatchOptions::new().arg_limit(11)).unwrap();
+
+    assert_eq!(
+        vec![0..2, 2..4, 4..5],
+        reports.iter().map(|report| report.arguments.clone()).collect::<Vec<_>>()
+    );
+    assert!(reports.iter().all(|report| report.success()));
+}
+
+#[test]
+fn execute_batches_default_limit_without_environment() {
+    // The environment is not a part of the command line, so it does not take space from the arguments.
+    let factory = || {
+        let mut command = list_tests();
+
+        command.env("EXECUTE_BATCHES_LARGE_VARIABLE", "a".repeat(30000));
+
+        command
+    };
+
+    // Together, the arguments are much longer than a command line.
+    let args: Vec<String> = (0..2000).map(|i| format!("{i:0>100}")).collect();
+
+    let reports = execute::execute_batches(factory, &args, BatchOptions::new()).unwrap();
+
+    assert!(reports.len() > 1);
+    assert!(reports.iter().all(|report| report.success()));
+    assert_eq!(2000, reports.last().unwrap().arguments.end);
+}
@@ -1,11 +1,18 @@
 #![cfg(target_os = "linux")]
 
 use std::{
-    io::Cursor,
-    process::{Command, Stdio},
+    env, fs,
+    io::{self, BufRead, BufReader, Cursor, ErrorKind},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    process::{self, Command, Output, Stdio},
+    time::{Duration, Instant},
 };
 
-use execute::Execute;
+use execute::{
+    ChildGuard, Execute, LimitExceeded, NoMatchPolicy, ParseOptions, PtyOptions, Redirect,
+    ResourceLimits, Session, Stream, Termination, TerminationPolicy, WriteMode,
+};
 
 const LARGE_INPUT_SIZE: usize = 1024 * 1024;
 
@@ -109,6 +116,140 @@ fn execute_input_reader_output_with_const_buffer_size() {
     assert_eq!(b"abc", output.stdout.as_slice());
 }
 
+#[test]
+fn execute_input_reader_output_with_capacity() {
+    let mut command = Command::new("cat");
+
+    command.stdout(Stdio::piped());
+
+    let mut reader = Cursor::new(vec![b'a'; LARGE_INPUT_SIZE]);
+
+    let output = command.execute_input_reader_output_with_capacity(&mut reader, 65536).unwrap();
+
+    assert_eq!(LARGE_INPUT_SIZE, output.stdout.len());
+
+    let err = command.execute_input_reader_with_capacity(&mut reader, 0).unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn execute_input_buf_reader_output() {
+    let mut command = Command::new("cat");
+
+    command.stdout(Stdio::piped());
+
+    let mut reader = BufReader::with_capacity(3, Cursor::new(vec![b'a'; LARGE_INPUT_SIZE + 1]));
+
+    let output = command.execute_input_buf_reader_output(&mut reader).unwrap();
+
+    assert_eq!(LARGE_INPUT_SIZE + 1, output.stdout.len());
+}
+
+#[test]
+fn execute_input_file() {
+    let path = env::temp_dir().join(format!("execute-input-file-{}", process::id()));
+
+    fs::write(&path, "abc\ndef\n").unwrap();
+
+    let mut command = execute::shell("test -f /dev/stdin && cat");
+
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_input_file_output(&path).unwrap();
+
+    assert_eq!(b"abc\ndef\n", output.stdout.as_slice());
+
+    let mut command = Command::new("grep");
+
+    command.arg("-q").arg("def");
+
+    assert_eq!(Some(0), command.execute_input_file(&path).unwrap());
+
+    let err = command.execute_input_file(path.with_extension("missing")).unwrap_err();
+
+    assert_eq!(ErrorKind::NotFound, err.kind());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn execute_input_writer() {
+    let mut command = Command::new("grep");
+
+    command.arg("-q").arg("row-999");
+
+    let exit_code = command
+        .execute_input_writer(|stdin| {
+            for i in 0..1000 {
+                writeln!(stdin, "{i},row-{i}")?;
+            }
+
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(Some(0), exit_code);
+}
+
+#[test]
+fn execute_input_writer_output() {
+    let mut command = Command::new("cat");
+
+    command.stdout(Stdio::piped());
+
+    let output = command
+        .execute_input_writer_output(|stdin| {
+            for _ in 0..LARGE_INPUT_SIZE / 4 {
+                stdin.write_all(b"abc\n")?;
+            }
+
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(LARGE_INPUT_SIZE, output.stdout.len());
+}
+
+#[test]
+fn execute_input_writer_error() {
+    let mut command = Command::new("cat");
+
+    command.stdout(Stdio::piped());
+
+    let err = command
+        .execute_input_writer_output(|stdin| {
+            stdin.write_all(b"abc")?;
+
+            Err(io::Error::other("generator failed"))
+        })
+        .unwrap_err();
+
+    assert_eq!("generator failed", err.to_string());
+}
+
+#[test]
+fn execute_input_writer_panic() {
+    let marker = env::temp_dir().join(format!("execute-input-writer-panic-{}", process::id()));
+
+    let mut command = Command::new("sh");
+
+    command.arg("-c").arg("cat > /dev/null && touch \"$0\"").arg(&marker);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        command.execute_input_writer(|stdin| {
+            stdin.write_all(b"abc")?;
+
+            panic!("the generator panics");
+        })
+    }));
+
+    assert!(result.is_err());
+
+    // The process was killed before stdin was closed, so it never finished reading.
+    assert!(!marker.exists());
+}
+
 #[test]
 fn execute_input_reader_output_with_large_input() {
     let mut command = Command::new("cat");
@@ -122,3 +263,474 @@ fn execute_input_reader_output_with_large_input() {
 
     assert_eq!(data, output.stdout);
 }
+
+#[test]
+fn command_with_glob() {
+    let mut command = execute::command_with_glob(
+        "ls -d 'sys'*",
+        ParseOptions::new(),
+        "/proc",
+        NoMatchPolicy::Error,
+    )
+    .unwrap();
+
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_output().unwrap();
+
+    assert!(output.stdout.starts_with(b"sys"));
+
+    assert!(
+        execute::command_with_glob(
+            "ls *.nothing",
+            ParseOptions::new(),
+            "/proc",
+            NoMatchPolicy::Error
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn command_with_env_assignments() {
+    let mut command =
+        execute::command("EXECUTE_A=1 EXECUTE_B='2 3' sh -c 'echo \"$EXECUTE_A $EXECUTE_B\"'");
+
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_output().unwrap();
+
+    assert_eq!(b"1 2 3\n", output.stdout.as_slice());
+}
+
+#[test]
+fn command_with_input() {
+    let (mut command, input) =
+        execute::command_with_input("cat <<<'hello world'", ParseOptions::new());
+
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_input_output(&input.unwrap()).unwrap();
+
+    assert_eq!(b"hello world\n", output.stdout.as_slice());
+}
+
+#[test]
+fn child_guard() {
+    let guard = ChildGuard::new(Command::new("sleep").arg("60").spawn().unwrap());
+    let stat_path = format!("/proc/{}/stat", guard.id());
+
+    assert!(Path::new(&stat_path).exists());
+
+    drop(guard);
+
+    // The child has been killed and reaped, so it no longer exists at all.
+    assert!(!Path::new(&stat_path).exists());
+
+    let mut guard =
+        ChildGuard::new(Command::new("echo").arg("abc").stdout(Stdio::piped()).spawn().unwrap());
+
+    let output = guard.wait_with_output().unwrap();
+
+    assert_eq!(b"abc\n", output.stdout.as_slice());
+}
+
+#[test]
+fn child_guard_terminate() {
+    let mut command =
+        execute::shell("trap 'echo flushed; exit 0' TERM; echo ready; while :; do sleep 0.1; done");
+
+    command.stdout(Stdio::piped());
+
+    let mut guard = ChildGuard::new(command.spawn().unwrap());
+    let mut stdout = BufReader::new(guard.stdout.take().unwrap());
+    let mut line = String::new();
+
+    stdout.read_line(&mut line).unwrap();
+
+    assert_eq!("ready\n", line);
+    assert_eq!(None, guard.wait_timeout(Duration::from_millis(100)).unwrap());
+    assert_eq!(Termination::Signal, guard.terminate().unwrap());
+
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+
+    assert_eq!("flushed\n", line);
+    assert_eq!(Some(0), guard.wait().unwrap().code());
+    assert_eq!(Termination::AlreadyExited, guard.terminate().unwrap());
+}
+
+#[test]
+fn child_guard_terminate_kill() {
+    let mut command = execute::shell("trap '' TERM; echo ready; while :; do sleep 0.1; done");
+
+    command.stdout(Stdio::piped());
+
+    let mut guard = ChildGuard::new(command.spawn().unwrap());
+
+    guard.set_termination_policy(TerminationPolicy::new().grace_period(Duration::from_millis(200)));
+
+    BufReader::new(guard.stdout.take().unwrap()).read_line(&mut String::new()).unwrap();
+
+    assert_eq!(Termination::Kill, guard.terminate().unwrap());
+    assert_eq!(None, guard.wait().unwrap().code());
+}
+
+#[test]
+fn child_guard_drop_kills_without_grace_period() {
+    let mut command = execute::shell("trap '' TERM; echo ready; while :; do sleep 0.1; done");
+
+    command.stdout(Stdio::piped());
+
+    let mut guard = ChildGuard::new(command.spawn().unwrap());
+    let stat_path = format!("/proc/{}/stat", guard.id());
+
+    BufReader::new(guard.stdout.take().unwrap()).read_line(&mut String::new()).unwrap();
+
+    let started = Instant::now();
+
+    drop(guard);
+
+    assert!(started.elapsed() < TerminationPolicy::DEFAULT_GRACE_PERIOD);
+    assert!(!Path::new(&stat_path).exists());
+}
+
+#[test]
+fn execute_status() {
+    let mut command = Command::new("true");
+
+    let status = command.execute_status().unwrap();
+
+    assert!(status.success());
+    assert_eq!("exit status: 0", status.to_string());
+
+    let mut command = execute::shell("kill -SEGV $$");
+
+    let status = command.execute_status().unwrap();
+
+    assert_eq!(None, status.code());
+    assert_eq!(Some(11), status.signal());
+    assert_eq!(Some("SIGSEGV"), status.signal_name());
+    assert!(!status.stopped());
+    assert!(status.to_string().starts_with("signal: 11 (SIGSEGV)"));
+}
+
+#[test]
+fn execute_with_resource_usage() {
+    let mut command = execute::shell("i=0; while [ $i -lt 100000 ]; do i=$((i + 1)); done; exit 3");
+
+    let status = command.execute_with_resource_usage().unwrap();
+
+    assert_eq!(Some(3), status.code());
+
+    let resource_usage = status.resource_usage().unwrap();
+
+    assert!(resource_usage.max_rss() > 0);
+    assert!(!(resource_usage.user_time() + resource_usage.system_time()).is_zero());
+
+    let mut guard = ChildGuard::new(Command::new("true").spawn().unwrap());
+
+    assert!(guard.wait_with_resource_usage().unwrap().resource_usage().is_some());
+    assert!(guard.wait().unwrap().success());
+    assert_eq!(Termination::AlreadyExited, guard.terminate().unwrap());
+}
+
+#[test]
+fn execute_output_with_resource_usage() {
+    let mut command = execute::shell("echo abc; echo def >&2; exit 3");
+
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    let (output, status) = command.execute_output_with_resource_usage().unwrap();
+
+    assert_eq!(b"abc\n", output.stdout.as_slice());
+    assert_eq!(b"def\n", output.stderr.as_slice());
+    assert_eq!(Some(3), output.status.code());
+    assert_eq!(Some(3), status.code());
+    assert!(status.resource_usage().unwrap().max_rss() > 0);
+}
+
+#[test]
+fn resource_limits() {
+    let mut command = execute::shell("while :; do :; done");
+
+    ResourceLimits::new().cpu_time(1).apply(&mut command);
+
+    let status = command.execute_status().unwrap();
+
+    assert_eq!(Some("SIGXCPU"), status.signal_name());
+    assert_eq!(Some(LimitExceeded::CpuTime), status.limit_exceeded());
+    assert!(status.to_string().contains("(CPU limit exceeded)"));
+}
+
+#[test]
+fn execute_pty_output() {
+    let mut command = execute::shell("[ -t 1 ] && echo tty; stty size; echo error >&2");
+
+    let output = command.execute_pty_output(PtyOptions::new().window_size(40, 120)).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(b"tty\r\n40 120\r\nerror\r\n", output.stdout.as_slice());
+    assert!(output.stderr.is_empty());
+
+    // The command can still be executed without a terminal afterwards.
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::null());
+
+    let output = command.execute_output().unwrap();
+
+    assert_eq!(b"", output.stdout.as_slice());
+}
+
+#[test]
+fn execute_pty_output_resets_command() {
+    let mut command = execute::shell(
+        r#"set -- $(cat /proc/$$/stat); [ "$6" = $$ ] && echo leader; readlink /proc/$$/fd/0 /proc/$$/fd/2"#,
+    );
+
+    let output = command.execute_pty_output(PtyOptions::new()).unwrap();
+
+    assert!(output.stdout.starts_with(b"leader\r\n"));
+
+    // The process no longer leads a new session, and its stdin and stderr are inherited from the parent again.
+    command.stdout(Stdio::piped());
+
+    let output = command.execute_output().unwrap();
+
+    assert_eq!(
+        format!(
+            "{}\n{}\n",
+            fs::read_link("/proc/self/fd/0").unwrap().display(),
+            fs::read_link("/proc/self/fd/2").unwrap().display()
+        ),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+#[test]
+fn execute_pty_input_output() {
+    let mut command = Command::new("cat");
+
+    let output = command.execute_pty_input_output("abc\ndef", PtyOptions::new()).unwrap();
+
+    assert_eq!(b"abc\r\ndef", output.stdout.as_slice());
+
+    let output = command.execute_pty_input_output("abc\n", PtyOptions::new().echo(true)).unwrap();
+
+    assert_eq!(b"abc\r\nabc\r\n", output.stdout.as_slice());
+
+    let mut command = Command::new("true");
+
+    let output =
+        command.execute_pty_input_output(&"abc\n".repeat(100000), PtyOptions::new()).unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn session() {
+    let mut session = Session::spawn(&mut Command::new("cat")).unwrap();
+
+    session.send_line("hello").unwrap();
+
+    assert_eq!(b"hello\n", session.read_until(b'\n', Duration::from_secs(5)).unwrap().as_slice());
+
+    session.send("abc def").unwrap();
+
+    assert_eq!(b"abc", session.expect("abc", Duration::from_secs(5)).unwrap().as_slice());
+
+    let err = session.expect("ghi", Duration::from_millis(100)).unwrap_err();
+
+    assert_eq!(ErrorKind::TimedOut, err.kind());
+
+    session.send_line(" ghi").unwrap();
+
+    assert_eq!(b" def ghi", session.expect("ghi", Duration::from_secs(5)).unwrap().as_slice());
+
+    assert!(session.wait().unwrap().success());
+    assert_eq!(b"\n", session.read_to_end(Duration::from_secs(5)).unwrap().as_slice());
+
+    let err = session.expect("anything", Duration::from_secs(5)).unwrap_err();
+
+    assert_eq!(ErrorKind::UnexpectedEof, err.kind());
+}
+
+#[test]
+fn session_pattern_across_chunks() {
+    let mut session = Session::spawn(&mut execute::shell(
+        "printf xxab; sleep 0.2; printf c; sleep 0.2; printf dyy; sleep 0.2; printf abcd",
+    ))
+    .unwrap();
+
+    assert_eq!(b"xxabcd", session.expect("abcd", Duration::from_secs(5)).unwrap().as_slice());
+    assert_eq!(b"yyabcd", session.expect("abcd", Duration::from_secs(5)).unwrap().as_slice());
+
+    let start = Instant::now();
+
+    drop(Session::spawn(Command::new("sleep").arg("10")).unwrap());
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn session_pty() {
+    let mut command = execute::shell(
+        "[ -t 0 ] || exit 1; while printf 'name? '; read name; do echo \"hi $name\"; done",
+    );
+
+    let mut session = Session::spawn_pty(&mut command, PtyOptions::new()).unwrap();
+
+    for name in ["alice", "bob"] {
+        session.expect("name? ", Duration::from_secs(5)).unwrap();
+        session.send_line(name).unwrap();
+
+        let greeting = session.read_until(b'\n', Duration::from_secs(5)).unwrap();
+
+        assert_eq!(format!("hi {name}\r\n").as_bytes(), greeting.as_slice());
+    }
+
+    session.expect("name? ", Duration::from_secs(5)).unwrap();
+
+    assert!(session.wait().unwrap().success());
+}
+
+#[test]
+fn redirect_files() {
+    let dir = env::temp_dir().join(format!("execute-redirect-{}", process::id()));
+
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("input");
+    let output = dir.join("output");
+    let log = dir.join("log");
+
+    fs::write(&input, "abc\n").unwrap();
+
+    let mut command = execute::shell("cat; echo error >&2");
+
+    command.stdin_file(&input).unwrap().stdout_file(&output, WriteMode::Truncate).unwrap();
+    command.stderr_file(&log, WriteMode::Append).unwrap();
+
+    assert_eq!(Some(0), command.execute_output().unwrap().status.code());
+
+    command.stdin_file(&input).unwrap().stdout_file(&output, WriteMode::Append).unwrap();
+    command.stderr_file(&log, WriteMode::Append).unwrap();
+
+    command.execute_output().unwrap();
+
+    assert_eq!("abc\nabc\n", fs::read_to_string(&output).unwrap());
+    assert_eq!("error\nerror\n", fs::read_to_string(&log).unwrap());
+
+    let mut command = execute::shell("echo a; echo b >&2; echo c");
+
+    command.stdout_stderr_file(&output, WriteMode::Truncate).unwrap();
+    command.execute_output().unwrap();
+
+    assert_eq!("a\nb\nc\n", fs::read_to_string(&output).unwrap());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn execute_output_merged() {
+    let mut command = execute::shell("echo a; echo b >&2; echo c");
+
+    let output = command.execute_output_merged().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(b"a\nb\nc\n", output.stdout.as_slice());
+    assert!(output.stderr.is_empty());
+
+    // The read end of the pipe is not inherited, so the process sees no more descriptors than usual.
+    let mut command = Command::new("ls");
+
+    command.arg("/proc/self/fd");
+    command.stdout(Stdio::piped());
+
+    let expected = command.execute_output().unwrap();
+
+    let output = command.execute_output_merged().unwrap();
+
+    assert_eq!(expected.stdout, output.stdout);
+}
+
+#[test]
+fn execute_transcript() {
+    let mut command = execute::shell("echo a; sleep 0.1; echo b >&2; sleep 0.1; echo c; exit 3");
+
+    let transcript = command.execute_transcript().unwrap();
+
+    assert_eq!(Some(3), transcript.status.code());
+
+    let chunks: Vec<(Stream, &[u8])> =
+        transcript.chunks.iter().map(|chunk| (chunk.stream, chunk.data.as_slice())).collect();
+
+    assert_eq!(
+        vec![
+            (Stream::Stdout, b"a\n".as_slice()),
+            (Stream::Stderr, b"b\n".as_slice()),
+            (Stream::Stdout, b"c\n".as_slice()),
+        ],
+        chunks
+    );
+    assert!(transcript.chunks.windows(2).all(|w| w[0].elapsed <= w[1].elapsed));
+    assert!(transcript.chunks[2].elapsed >= Duration::from_millis(200));
+
+    let output = Output::from(transcript);
+
+    assert_eq!(b"a\nc\n", output.stdout.as_slice());
+    assert_eq!(b"b\n", output.stderr.as_slice());
+}
+
+#[test]
+fn execute_output_to_writer() {
+    let mut command = execute::shell("head -c 1048576 /dev/zero; echo error >&2");
+
+    command.stderr(Stdio::piped());
+
+    let mut stdout = Vec::new();
+
+    let output = command.execute_output_to_writer(&mut stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(b"error\n", output.stderr.as_slice());
+    assert_eq!(LARGE_INPUT_SIZE, stdout.len());
+}
+
+struct FailingWriter {
+    remaining: usize,
+}
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Err(io::Error::other("writer failed"));
+        }
+
+        let length = buf.len().min(self.remaining);
+
+        self.remaining -= length;
+
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn execute_output_to_writer_error() {
+    // `yes` never ends by itself, so the process must be stopped by closing its stdout.
+    let mut command = Command::new("yes");
+
+    let err = command
+        .execute_output_to_writer(&mut FailingWriter {
+            remaining: 4096
+        })
+        .unwrap_err();
+
+    assert_eq!("writer failed", err.to_string());
+}
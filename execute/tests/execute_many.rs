@@ -1,11 +1,16 @@
 #![cfg(target_os = "linux")]
 
 use std::{
-    io::Cursor,
-    process::{Command, Stdio},
+    env, fs,
+    io::{self, BufRead, BufReader, Cursor},
+    os::unix::process::CommandExt,
+    panic::{self, AssertUnwindSafe},
+    process::{self, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-use execute::Execute;
+use execute::{Execute, LimitExceeded, ResourceLimits, Stage, Stream, Termination};
 
 const LARGE_INPUT_SIZE: usize = 1024 * 1024;
 
@@ -53,6 +58,22 @@ fn execute_multiple_output() {
     assert!(output.stdout.starts_with(b"cpu MHz"));
 }
 
+#[test]
+fn execute_multiple_output_closes_upstream_pipes() {
+    // `yes` only stops when it gets `SIGPIPE`, after every read end of its pipe has been closed.
+    let mut command1 = Command::new("yes");
+
+    let mut command2 = Command::new("head");
+
+    command2.arg("-n").arg("2");
+
+    command2.stdout(Stdio::piped());
+
+    let output = command1.execute_multiple_output(&mut [&mut command2]).unwrap();
+
+    assert_eq!(b"y\ny\n", output.stdout.as_slice());
+}
+
 #[test]
 fn execute_multiple_input() {
     let mut command1 = Command::new("bc");
@@ -138,3 +159,554 @@ fn execute_multiple_input_reader_output() {
 
     assert_eq!(b"2\n", output.stdout.as_slice());
 }
+
+#[test]
+fn execute_multiple_input_reader_output_with_capacity() {
+    let mut command1 = Command::new("cat");
+
+    let mut command2 = Command::new("wc");
+
+    command2.arg("-c");
+
+    command2.stdout(Stdio::piped());
+
+    let mut reader = Cursor::new(vec![b'a'; LARGE_INPUT_SIZE]);
+
+    let output = command1
+        .execute_multiple_input_reader_output_with_capacity(&mut reader, 65536, &mut [
+            &mut command2,
+        ])
+        .unwrap();
+
+    assert_eq!(LARGE_INPUT_SIZE.to_string(), String::from_utf8(output.stdout).unwrap().trim());
+}
+
+#[test]
+fn execute_multiple_input_buf_reader_output() {
+    let mut command1 = Command::new("cat");
+
+    let mut command2 = Command::new("tr");
+
+    command2.arg("a-z").arg("A-Z");
+
+    command2.stdout(Stdio::piped());
+
+    let mut reader = BufReader::new(Cursor::new("abc"));
+
+    let output = command1
+        .execute_multiple_input_buf_reader_output(&mut reader, &mut [&mut command2])
+        .unwrap();
+
+    assert_eq!(b"ABC", output.stdout.as_slice());
+}
+
+#[test]
+fn execute_multiple_input_file_output() {
+    let path = env::temp_dir().join(format!("execute-multiple-input-file-{}", process::id()));
+
+    fs::write(&path, "abc\n").unwrap();
+
+    let mut command1 = Command::new("cat");
+
+    let mut command2 = Command::new("tr");
+
+    command2.arg("a-z").arg("A-Z");
+
+    command2.stdout(Stdio::piped());
+
+    let output = command1.execute_multiple_input_file_output(&path, &mut [&mut command2]).unwrap();
+
+    assert_eq!(b"ABC\n", output.stdout.as_slice());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn execute_multiple_input_writer_output() {
+    let mut command1 = Command::new("cat");
+
+    let mut command2 = Command::new("grep");
+
+    command2.arg("-c").arg("^even");
+
+    command2.stdout(Stdio::piped());
+
+    let output = command1
+        .execute_multiple_input_writer_output(
+            |stdin| {
+                for i in 0..1000 {
+                    writeln!(stdin, "{},{i}", if i % 2 == 0 { "even" } else { "odd" })?;
+                }
+
+                Ok(())
+            },
+            &mut [&mut command2],
+        )
+        .unwrap();
+
+    assert_eq!(b"500\n", output.stdout.as_slice());
+}
+
+#[test]
+fn spawn_multiple() {
+    let mut command1 = Command::new("echo");
+
+    command1.arg("abc");
+
+    let mut command2 = Command::new("tr");
+
+    command2.arg("a-z").arg("A-Z");
+
+    command2.stdout(Stdio::piped());
+
+    let pipeline = command1.spawn_multiple(&mut [&mut command2]).unwrap();
+
+    assert_eq!(1, pipeline.upstream_children().len());
+    assert!(pipeline.process_group().is_none());
+
+    assert_eq!(b"ABC\n", pipeline.wait_with_output().unwrap().stdout.as_slice());
+}
+
+fn is_process_alive(pid: &str) -> bool {
+    // A zombie whose parent has not reaped it yet is not alive.
+    match fs::read_to_string(format!("/proc/{pid}/stat")) {
+        Ok(stat) => !stat.rsplit(") ").next().unwrap().starts_with('Z'),
+        Err(_) => false,
+    }
+}
+
+#[test]
+fn spawn_multiple_in_process_group_kills_grandchildren() {
+    let mut command1 = Command::new("sh");
+
+    command1.arg("-c").arg("sleep 60 & echo $!; wait");
+
+    let mut command2 = Command::new("cat");
+
+    command2.stdout(Stdio::piped());
+
+    let mut pipeline = command1.spawn_multiple_in_process_group(&mut [&mut command2]).unwrap();
+
+    let process_group = pipeline.process_group().unwrap();
+
+    assert_eq!(pipeline.upstream_children()[0].id(), process_group.id());
+
+    let mut stdout = BufReader::new(pipeline.last_child_mut().stdout.take().unwrap());
+    let mut grandchild_pid = String::new();
+
+    stdout.read_line(&mut grandchild_pid).unwrap();
+
+    let grandchild_pid = grandchild_pid.trim_end();
+
+    assert!(is_process_alive(grandchild_pid));
+
+    pipeline.kill().unwrap();
+
+    assert_eq!(None, pipeline.wait().unwrap());
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while is_process_alive(grandchild_pid) {
+        assert!(Instant::now() < deadline, "the grandchild {grandchild_pid} survived");
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn process_group_id(stat: &str) -> &str {
+    stat.rsplit(") ").next().unwrap().split(' ').nth(2).unwrap()
+}
+
+#[test]
+fn spawn_multiple_in_process_group_leaves_commands_unchanged() {
+    let mut command1 = execute::shell("cat /proc/$$/stat");
+
+    let mut command2 = Command::new("cat");
+
+    command2.stdout(Stdio::piped());
+
+    let pipeline = command1.spawn_multiple_in_process_group(&mut [&mut command2]).unwrap();
+
+    let process_group = pipeline.process_group().unwrap();
+
+    let output = pipeline.wait_with_output().unwrap();
+
+    assert_eq!(
+        process_group.id().to_string(),
+        process_group_id(&String::from_utf8(output.stdout).unwrap())
+    );
+
+    let output = command1.execute_output().unwrap();
+
+    assert_eq!(
+        process_group_id(&fs::read_to_string("/proc/self/stat").unwrap()),
+        process_group_id(&String::from_utf8(output.stdout).unwrap())
+    );
+
+    // Spawning the same commands in a process group again puts them into another new group.
+    let pipeline = command1.spawn_multiple_in_process_group(&mut [&mut command2]).unwrap();
+
+    let new_process_group = pipeline.process_group().unwrap();
+
+    let output = pipeline.wait_with_output().unwrap();
+
+    assert_ne!(process_group.id(), new_process_group.id());
+    assert_eq!(
+        new_process_group.id().to_string(),
+        process_group_id(&String::from_utf8(output.stdout).unwrap())
+    );
+}
+
+#[test]
+fn pipeline_drop_kills_children_during_panic() {
+    let mut command1 = Command::new("sleep");
+
+    command1.arg("60");
+
+    let mut command2 = Command::new("cat");
+
+    let pipeline = command1.spawn_multiple(&mut [&mut command2]).unwrap();
+
+    let pids = [pipeline.upstream_children()[0].id(), pipeline.last_child().id()];
+
+    let result = panic::catch_unwind(AssertUnwindSafe(move || {
+        let _pipeline = pipeline;
+
+        panic!("the pipeline is dropped while unwinding");
+    }));
+
+    assert!(result.is_err());
+
+    // The children have been killed and reaped, so they no longer exist at all.
+    for pid in pids {
+        assert!(!is_process_alive(&pid.to_string()));
+    }
+}
+
+#[test]
+fn execute_multiple_terminates_stages_when_a_stage_fails_to_spawn() {
+    let marker = env::temp_dir().join(format!("execute-stage-sigterm-{}", process::id()));
+
+    let _ = fs::remove_file(&marker);
+
+    let mut command1 =
+        execute::shell(r#"trap 'kill $!; touch "$0"; exit 0' TERM; sleep 60 & wait"#);
+
+    command1.arg(&marker);
+
+    let mut command2 = Command::new("cat");
+
+    // The second stage fails to spawn only after the first one has set up its trap.
+    // SAFETY: The closure only sleeps with `nanosleep`, which is async-signal-safe.
+    unsafe {
+        command2.pre_exec(|| {
+            thread::sleep(Duration::from_millis(500));
+
+            Err(io::Error::other("failed"))
+        });
+    }
+
+    assert!(command1.execute_multiple(&mut [&mut command2]).is_err());
+
+    // The first stage has handled `SIGTERM` rather than being killed with `SIGKILL`.
+    assert!(marker.exists());
+
+    fs::remove_file(&marker).unwrap();
+}
+
+#[test]
+fn pipeline_terminate() {
+    let mut command1 = Command::new("sleep");
+
+    command1.arg("60");
+
+    let mut command2 = Command::new("cat");
+
+    let mut pipeline = command1.spawn_multiple(&mut [&mut command2]).unwrap();
+
+    assert_eq!(None, pipeline.wait_timeout(Duration::from_millis(100)).unwrap());
+    assert_eq!(Termination::Signal, pipeline.terminate().unwrap());
+    assert_eq!(None, pipeline.wait().unwrap());
+}
+
+#[test]
+fn execute_multiple_status() {
+    let mut command1 = Command::new("echo");
+
+    command1.arg("abc");
+
+    let mut command2 = execute::shell("kill -KILL $$");
+
+    let status = command1.execute_multiple_status(&mut [&mut command2]).unwrap();
+
+    assert_eq!(Some(9), status.signal());
+    assert_eq!(Some("SIGKILL"), status.signal_name());
+    assert!(!status.core_dumped());
+}
+
+#[test]
+fn execute_multiple_with_resource_usage() {
+    let mut command1 = Command::new("echo");
+
+    command1.arg("abc");
+
+    let mut command2 = Command::new("cat");
+    // Read all of the input first, so `cat` is not killed by `SIGPIPE`.
+    let mut command3 = execute::shell("cat > /dev/null; exit 1");
+
+    let statuses =
+        command1.execute_multiple_with_resource_usage(&mut [&mut command2, &mut command3]).unwrap();
+
+    assert_eq!(
+        vec![Some(0), Some(0), Some(1)],
+        statuses.iter().map(|status| status.code()).collect::<Vec<_>>()
+    );
+
+    for status in statuses {
+        assert!(status.resource_usage().unwrap().max_rss() > 0);
+    }
+}
+
+#[test]
+fn execute_multiple_output_with_resource_usage() {
+    let mut command1 = Command::new("echo");
+
+    command1.arg("abc");
+
+    let mut command2 = Command::new("tr");
+
+    command2.arg("a-z").arg("A-Z");
+    command2.stdout(Stdio::piped());
+
+    let (output, statuses) =
+        command1.execute_multiple_output_with_resource_usage(&mut [&mut command2]).unwrap();
+
+    assert_eq!(b"ABC\n", output.stdout.as_slice());
+    assert!(output.status.success());
+    assert_eq!(2, statuses.len());
+
+    for status in statuses {
+        assert!(status.success());
+        assert!(status.resource_usage().unwrap().max_rss() > 0);
+    }
+}
+
+#[test]
+fn execute_multiple_resource_limits() {
+    let path = env::temp_dir().join(format!("execute-resource-limits-{}", process::id()));
+
+    let mut command1 = Command::new("head");
+
+    command1.args(["-c", "10000", "/dev/zero"]);
+
+    let mut command2 = Command::new("dd");
+
+    command2.arg(format!("of={}", path.to_str().unwrap()));
+
+    ResourceLimits::new().file_size(1000).apply(&mut command2);
+
+    let status = command1.execute_multiple_status(&mut [&mut command2]).unwrap();
+
+    let file_size = fs::metadata(&path).unwrap().len();
+
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(Some(LimitExceeded::FileSize), status.limit_exceeded());
+    assert_eq!(1000, file_size);
+}
+
+#[test]
+fn execute_multiple_output_merged() {
+    let mut command1 = Command::new("echo");
+
+    command1.arg("abc");
+
+    let mut command2 = execute::shell("cat; echo error >&2; exit 2");
+
+    let output = command1.execute_multiple_output_merged(&mut [&mut command2]).unwrap();
+
+    assert_eq!(Some(2), output.status.code());
+    assert_eq!(b"abc\nerror\n", output.stdout.as_slice());
+}
+
+#[test]
+fn execute_multiple_transcript() {
+    let mut command1 = execute::shell("echo abc; echo ignored >&2");
+
+    let mut command2 = execute::shell("cat; sleep 0.1; echo error >&2; exit 2");
+
+    let transcript = command1.execute_multiple_transcript(&mut [&mut command2]).unwrap();
+
+    assert_eq!(Some(2), transcript.status.code());
+    assert_eq!(2, transcript.chunks.len());
+    assert_eq!(Stream::Stdout, transcript.chunks[0].stream);
+    assert_eq!(Stream::Stderr, transcript.chunks[1].stream);
+    assert_eq!(b"abc\n", transcript.stdout().as_slice());
+    assert_eq!(b"error\n", transcript.stderr().as_slice());
+}
+
+#[test]
+fn execute_multiple_output_to_writer() {
+    let mut command1 = Command::new("yes");
+
+    let mut command2 = Command::new("head");
+
+    command2.arg("-c").arg(LARGE_INPUT_SIZE.to_string());
+
+    let mut stdout = Vec::new();
+
+    let output =
+        command1.execute_multiple_output_to_writer(&mut stdout, &mut [&mut command2]).unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(LARGE_INPUT_SIZE, stdout.len());
+    assert!(stdout.starts_with(b"y\ny\n"));
+}
+
+#[test]
+fn execute_multiple_stages_output() {
+    let mut command1 = Command::new("printf");
+
+    command1.arg("1\n2\n3\n4\n");
+
+    let mut command2 = Command::new("tr");
+
+    command2.arg("0-9").arg("a-j");
+
+    command2.stdout(Stdio::piped());
+
+    let output = command1
+        .execute_multiple_stages_output(vec![
+            Stage::function(|input, output| {
+                for line in BufReader::new(input).lines() {
+                    let number: u32 = line?.parse().unwrap();
+
+                    if number >= 3 {
+                        writeln!(output, "{}", number * 2)?;
+                    }
+                }
+
+                Ok(())
+            }),
+            Stage::function(|input, output| {
+                io::copy(input, output)?;
+
+                Ok(())
+            }),
+            Stage::from(&mut command2),
+        ])
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(b"g\ni\n", output.stdout.as_slice());
+}
+
+#[test]
+fn execute_multiple_stages_error() {
+    // `yes` never ends by itself, so it must be stopped by the function closing its input.
+    let mut command1 = Command::new("yes");
+
+    let mut command2 = Command::new("cat");
+
+    let err = command1
+        .execute_multiple_stages(vec![
+            Stage::function(|input, _| {
+                input.read_exact(&mut [0u8; 4096])?;
+
+                Err(io::Error::other("stage failed"))
+            }),
+            Stage::from(&mut command2),
+        ])
+        .unwrap_err();
+
+    assert_eq!("stage failed", err.to_string());
+}
+
+#[test]
+fn execute_multiple_stages_broken_pipe() {
+    let mut command1 = Command::new("true");
+
+    let mut command2 = Command::new("head");
+
+    command2.arg("-c").arg("4");
+
+    command2.stdout(Stdio::piped());
+
+    let output = command1
+        .execute_multiple_stages_output(vec![
+            Stage::function(|_, output| {
+                loop {
+                    output.write_all(b"y\n")?;
+                }
+            }),
+            Stage::from(&mut command2),
+        ])
+        .unwrap();
+
+    assert_eq!(b"y\ny\n", output.stdout.as_slice());
+}
+
+#[test]
+fn execute_multiple_stages_last_function() {
+    let mut command1 = Command::new("true");
+
+    let err = command1.execute_multiple_stages(vec![Stage::function(|_, _| Ok(()))]).unwrap_err();
+
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn execute_tee_output() {
+    let mut command = Command::new("printf");
+
+    command.arg("abc\ndef\n");
+
+    let mut branch1 = Command::new("wc");
+
+    branch1.arg("-l");
+
+    branch1.stdout(Stdio::piped());
+
+    let mut branch2 = Command::new("tr");
+
+    branch2.arg("a-z").arg("A-Z");
+
+    branch2.stdout(Stdio::piped());
+
+    let output = command.execute_tee_output(&mut [&mut branch1, &mut branch2]).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(2, output.branches.len());
+    assert_eq!("2", String::from_utf8_lossy(&output.branches[0].stdout).trim());
+    assert_eq!(b"ABC\nDEF\n", output.branches[1].stdout.as_slice());
+}
+
+#[test]
+fn execute_multiple_tee_output() {
+    let mut command1 = Command::new("head");
+
+    command1.arg("-c").arg(LARGE_INPUT_SIZE.to_string()).arg("/dev/zero");
+
+    let mut command2 = Command::new("cat");
+
+    // This branch exits without reading its input, which must not stop the other one.
+    let mut branch1 = Command::new("true");
+
+    let mut branch2 = Command::new("wc");
+
+    branch2.arg("-c");
+
+    branch2.stdout(Stdio::piped());
+
+    let output = command1
+        .execute_multiple_tee_output(&mut [&mut command2], &mut [&mut branch1, &mut branch2])
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(output.branches[0].status.success());
+    assert_eq!(
+        LARGE_INPUT_SIZE.to_string(),
+        String::from_utf8_lossy(&output.branches[1].stdout).trim()
+    );
+}
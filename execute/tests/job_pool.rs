@@ -0,0 +1,136 @@
+#![cfg(target_os = "linux")]
+
+use std::{
+    env, fs,
+    process::{self, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use execute::{Job, JobOrder, JobPool, TerminationPolicy};
+
+fn sleep_and_echo(seconds: &str, text: &str) -> Command {
+    let mut command = execute::shell(format!("sleep {seconds}; echo {text}"));
+
+    command.stdout(Stdio::piped());
+
+    command
+}
+
+#[test]
+fn job_pool_completion_order() {
+    let mut pool = JobPool::new();
+
+    pool.set_concurrency(3);
+
+    let started = Instant::now();
+
+    let reports: Vec<_> = pool
+        .run([sleep_and_echo("0.3", "a"), sleep_and_echo("0.1", "b"), sleep_and_echo("0.2", "c")])
+        .collect();
+
+    assert!(started.elapsed() < Duration::from_millis(600));
+    assert_eq!(vec![1, 2, 0], reports.iter().map(|report| report.index).collect::<Vec<_>>());
+    assert_eq!(b"b\n", reports[0].result.as_ref().unwrap().stdout.as_slice());
+}
+
+#[test]
+fn job_pool_input_order() {
+    let mut pool = JobPool::new();
+
+    pool.set_concurrency(2);
+    pool.set_order(JobOrder::Input);
+
+    let jobs = (0..6).map(|i| {
+        let mut command = Command::new("tr");
+
+        command.arg("a-z").arg("A-Z");
+
+        command.stdout(Stdio::piped());
+
+        Job::new(command).input(format!("job {i}"))
+    });
+
+    let reports: Vec<_> = pool.run(jobs).collect();
+
+    assert_eq!(6, reports.len());
+
+    for (i, report) in reports.iter().enumerate() {
+        assert_eq!(i, report.index);
+        assert_eq!(format!("JOB {i}").as_bytes(), report.result.as_ref().unwrap().stdout);
+    }
+}
+
+#[test]
+fn job_pool_fail_fast() {
+    let mut pool = JobPool::new();
+
+    pool.set_concurrency(2);
+    pool.set_fail_fast(true);
+    pool.set_termination_policy(TerminationPolicy::kill());
+
+    let started = Instant::now();
+
+    // A process forked by a shell would keep the pipe open after the shell is killed, so `sleep` is run directly.
+    let mut command = Command::new("sleep");
+
+    command.arg("10");
+
+    command.stdout(Stdio::piped());
+
+    let jobs = [
+        command,
+        execute::shell("sleep 0.1; exit 1"),
+        sleep_and_echo("0", "c"),
+        sleep_and_echo("0", "d"),
+    ];
+
+    let reports: Vec<_> = pool.run(jobs).collect();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // The jobs after the failed one have not been started.
+    assert_eq!(2, reports.len());
+    assert_eq!(1, reports[0].index);
+    assert_eq!(Some(1), reports[0].result.as_ref().unwrap().status.code());
+    assert_eq!(0, reports[1].index);
+    assert!(!reports[1].success());
+}
+
+#[test]
+fn job_pool_drop_terminates_jobs() {
+    let marker = env::temp_dir().join(format!("execute-job-pool-drop-{}", process::id()));
+
+    let _ = fs::remove_file(&marker);
+
+    let mut command = execute::shell(r#"trap 'kill $!; touch "$0"; exit 0' TERM; sleep 60 & wait"#);
+
+    command.arg(&marker);
+
+    let mut pool = JobPool::new();
+
+    pool.set_concurrency(2);
+
+    let started = Instant::now();
+
+    let mut results = pool.run([execute::shell("sleep 0.2"), command]);
+
+    assert_eq!(0, results.next().unwrap().index);
+
+    drop(results);
+
+    // The process has handled the termination signal before the results are dropped, well within the grace period.
+    assert!(marker.exists());
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    fs::remove_file(&marker).unwrap();
+}
+
+#[test]
+fn job_pool_spawn_error() {
+    let pool = JobPool::new();
+
+    let reports: Vec<_> = pool.run([Command::new("/nonexistent/program")]).collect();
+
+    assert_eq!(1, reports.len());
+    assert!(reports[0].result.is_err());
+}
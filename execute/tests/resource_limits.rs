@@ -0,0 +1,36 @@
+#![cfg(target_os = "linux")]
+
+// Lowering a hard limit of the test process cannot be undone, so this test has its own test binary.
+
+use std::process::Stdio;
+
+use execute::{Execute, ResourceLimits};
+
+const HARD_CPU_TIME: u64 = 86400;
+
+#[test]
+fn resource_limits_above_hard_limit() {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0, rlim_max: 0
+    };
+
+    // SAFETY: `limit` is valid for writes.
+    assert_eq!(0, unsafe { libc::getrlimit(libc::RLIMIT_CPU, &mut limit) });
+
+    limit.rlim_max = limit.rlim_max.min(HARD_CPU_TIME);
+    limit.rlim_cur = limit.rlim_cur.min(limit.rlim_max);
+
+    // SAFETY: `limit` is a valid `rlimit`.
+    assert_eq!(0, unsafe { libc::setrlimit(libc::RLIMIT_CPU, &limit) });
+
+    let mut command = execute::shell("ulimit -t; ulimit -H -t");
+
+    command.stdout(Stdio::piped());
+
+    ResourceLimits::new().cpu_time(HARD_CPU_TIME * 2).apply(&mut command);
+
+    let output = command.execute_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(format!("{0}\n{0}\n", limit.rlim_max), String::from_utf8(output.stdout).unwrap());
+}
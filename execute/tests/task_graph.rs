@@ -0,0 +1,129 @@
+#![cfg(target_os = "linux")]
+
+use std::{
+    io,
+    process::Command,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use execute::{Execute, FailurePolicy, TaskGraph, TaskOutcome};
+
+#[test]
+fn task_graph_order() {
+    let order = Mutex::new(Vec::new());
+
+    let mut graph: TaskGraph<()> = TaskGraph::new();
+
+    let record = |name: &'static str| {
+        let order = &order;
+
+        move || {
+            order.lock().unwrap().push(name);
+
+            Ok(())
+        }
+    };
+
+    let a = graph.add("a", &[], record("a"));
+    let b = graph.add("b", &[a], record("b"));
+    let c = graph.add("c", &[a], record("c"));
+    graph.add("d", &[b, c], record("d"));
+
+    let reports = graph.run();
+
+    assert!(reports.iter().all(|report| report.success()));
+    assert_eq!(
+        vec!["a", "b", "c", "d"],
+        reports.iter().map(|r| r.name.as_str()).collect::<Vec<_>>()
+    );
+
+    let order = order.into_inner().unwrap();
+
+    assert_eq!("a", order[0]);
+    assert_eq!("d", order[3]);
+}
+
+#[test]
+fn task_graph_concurrency() {
+    let running = AtomicUsize::new(0);
+    let max_running = AtomicUsize::new(0);
+
+    let mut graph: TaskGraph<()> = TaskGraph::new();
+
+    graph.set_concurrency(2);
+
+    for i in 0..6 {
+        graph.add(format!("task {i}"), &[], || {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+
+            max_running.fetch_max(now, Ordering::SeqCst);
+
+            thread::sleep(Duration::from_millis(50));
+
+            running.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(())
+        });
+    }
+
+    let started = Instant::now();
+
+    assert!(graph.run().iter().all(|report| report.success()));
+    assert_eq!(2, max_running.load(Ordering::SeqCst));
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
+
+#[test]
+fn task_graph_commands() {
+    let mut command1 = Command::new("true");
+    let mut command2 = Command::new("false");
+    let mut command3 = Command::new("true");
+    let mut command4 = Command::new("true");
+
+    let mut graph = TaskGraph::new();
+
+    graph.set_concurrency(1);
+
+    let a = graph.add_command("a", &[], &mut command1);
+    let b = graph.add_command("b", &[a], &mut command2);
+    graph.add_command("c", &[b], &mut command3);
+    graph.add_command("d", &[a], &mut command4);
+
+    let reports = graph.run();
+
+    assert!(reports[0].success());
+    assert!(
+        matches!(&reports[1].outcome, TaskOutcome::Completed(output) if output.status.code() == Some(1))
+    );
+    assert!(matches!(reports[2].outcome, TaskOutcome::Skipped));
+    // With one task at a time, `d` has not been started when `b` fails.
+    assert!(matches!(reports[3].outcome, TaskOutcome::Skipped));
+    assert_eq!(Duration::ZERO, reports[3].elapsed);
+}
+
+#[test]
+fn task_graph_keep_going() {
+    let mut graph: TaskGraph<Option<i32>> = TaskGraph::new();
+
+    graph.set_concurrency(1);
+    graph.set_failure_policy(FailurePolicy::KeepGoing);
+
+    let a = graph.add("a", &[], || Err(io::Error::other("task failed")));
+    graph.add("b", &[a], || Command::new("true").execute());
+    graph.add("c", &[], || Command::new("true").execute());
+    graph.add("d", &[], || panic!("task panicked"));
+
+    let reports = graph.run();
+
+    assert!(
+        matches!(&reports[0].outcome, TaskOutcome::Error(err) if err.to_string() == "task failed")
+    );
+    assert!(matches!(reports[1].outcome, TaskOutcome::Skipped));
+    assert!(reports[2].success());
+    assert!(matches!(reports[3].outcome, TaskOutcome::Error(_)));
+}